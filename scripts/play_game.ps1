@@ -73,14 +73,15 @@ Write-Host ""
 Write-Host "🎮 STEP 2: Creating game..." -ForegroundColor Cyan
 Write-Host ""
 
+$gameConfig = '{"stake_amount":"' + $StakeAmount + '","token":"' + $tokenAddress + '","rounds":1,"hide_progress":false,"commit_window":0,"reveal_window":0,"draw_policy":0,"reveal_order_enforced":false,"round_stakes":false,"ruleset":0,"invited":null,"use_balance":false,"p2_stake":null,"reveal_penalty_bps":0}'
+
 $gameIdOutput = stellar contract invoke `
     --id $contractId `
     --source $Player1 `
     --network $Network `
     -- create_game `
     --creator $Player1 `
-    --stake_amount $StakeAmount `
-    --token_address $tokenAddress 2>&1
+    --config $gameConfig 2>&1
 
 $gameId = $gameIdOutput | Select-Object -Last 1
 Write-Host "  ✅ Game created with ID: $gameId" -ForegroundColor Green
@@ -127,7 +128,7 @@ if (-not $nodeCheck) {
     Write-Host "  📖 Manual commitment generation:" -ForegroundColor Cyan
     Write-Host "     1. Choose move (1=Attack, 2=Defense, 3=Magic)" -ForegroundColor White
     Write-Host "     2. Generate random 32-byte salt" -ForegroundColor White
-    Write-Host "     3. Compute: SHA256(move_bytes || salt_bytes)" -ForegroundColor White
+    Write-Host "     3. Compute: SHA256(game_id_bytes || move_bytes || salt_bytes)" -ForegroundColor White
     Write-Host ""
     Write-Host "  ⏭️  Skipping commitment phase for this demo..." -ForegroundColor Yellow
     Write-Host ""
@@ -140,19 +141,19 @@ if (-not $nodeCheck) {
     Write-Host "  4. Anyone: call finalize_game to determine winner" -ForegroundColor White
     Write-Host ""
     Write-Host "Example commitment:" -ForegroundColor Yellow
-    Write-Host "  node scripts/utils/generate_commitment.js 1" -ForegroundColor Gray
+    Write-Host "  node scripts/utils/generate_commitment.js $gameId 1" -ForegroundColor Gray
     Write-Host ""
     exit 0
 }
 
 # Generate commitments using Node.js
 Write-Host "  🎲 Player 1 chooses: Attack (1)" -ForegroundColor White
-$p1Json = node scripts/utils/generate_commitment.js 1 2>&1 | ConvertFrom-Json
+$p1Json = node scripts/utils/generate_commitment.js $gameId 1 2>&1 | ConvertFrom-Json
 $p1Commitment = $p1Json.commitment
 $p1Salt = $p1Json.salt
 
 Write-Host "  🎲 Player 2 chooses: Magic (3)" -ForegroundColor White
-$p2Json = node scripts/utils/generate_commitment.js 3 2>&1 | ConvertFrom-Json
+$p2Json = node scripts/utils/generate_commitment.js $gameId 3 2>&1 | ConvertFrom-Json
 $p2Commitment = $p2Json.commitment
 $p2Salt = $p2Json.salt
 
@@ -6,37 +6,55 @@
  * scheme in Stellar Duels.
  * 
  * Usage:
- *   node generate_commitment.js <move_id>
+ *   node generate_commitment.js <game_id> <move_id> [--ruleset classic|extended]
  * 
  * Example:
- *   node generate_commitment.js 1  # Attack
- *   node generate_commitment.js 2  # Defense
- *   node generate_commitment.js 3  # Magic
+ *   node generate_commitment.js 7 1  # Attack in game 7
+ *   node generate_commitment.js 7 2  # Defense in game 7
+ *   node generate_commitment.js 7 3  # Magic in game 7
+ *   node generate_commitment.js 7 5 --ruleset extended  # Spock in an Extended game
  * ============================================================================
  */
 
 const crypto = require('crypto');
 
+// Moves available under each ruleset (Extended adds Lizard and Spock)
+const MOVE_NAMES = { 1: 'Attack', 2: 'Defense', 3: 'Magic', 4: 'Lizard', 5: 'Spock' };
+const MOVE_COUNTS = { classic: 3, extended: 5 };
+
 /**
  * Generate a cryptographic commitment for a move
- * @param {number} moveId - The move choice (1=Attack, 2=Defense, 3=Magic)
+ * @param {number} gameId - The game the commitment is for (bound into the hash)
+ * @param {number} moveId - The move choice (1=Attack, 2=Defense, 3=Magic, 4=Lizard, 5=Spock)
+ * @param {string} ruleset - The game's ruleset: 'classic' (moves 1-3) or 'extended' (moves 1-5)
  * @returns {Object} - { commitment: hex string, salt: hex string }
  */
-function generateCommitment(moveId) {
+function generateCommitment(gameId, moveId, ruleset = 'classic') {
+    const moveCount = MOVE_COUNTS[ruleset];
+    if (moveCount === undefined) {
+        throw new Error('Invalid ruleset. Must be classic or extended');
+    }
+    
     // Validate move
-    if (![1, 2, 3].includes(moveId)) {
-        throw new Error('Invalid move. Must be 1 (Attack), 2 (Defense), or 3 (Magic)');
+    if (!Number.isInteger(moveId) || moveId < 1 || moveId > moveCount) {
+        const allowed = Object.entries(MOVE_NAMES)
+            .slice(0, moveCount)
+            .map(([id, name]) => `${id} (${name})`)
+            .join(', ');
+        throw new Error(`Invalid move for the ${ruleset} ruleset. Must be one of ${allowed}`);
     }
     
     // Generate cryptographically secure random 32-byte salt
     const salt = crypto.randomBytes(32);
     
-    // Convert move_id to 4 bytes (big-endian, matching Rust's to_be_bytes)
+    // Convert game_id to 8 bytes and move_id to 4 bytes (big-endian, matching Rust's to_be_bytes)
+    const gameBuffer = Buffer.allocUnsafe(8);
+    gameBuffer.writeBigUInt64BE(BigInt(gameId));
     const moveBuffer = Buffer.allocUnsafe(4);
     moveBuffer.writeUInt32BE(moveId);
     
-    // Concatenate: 4 bytes (move) + 32 bytes (salt) = 36 bytes total
-    const data = Buffer.concat([moveBuffer, salt]);
+    // Concatenate: 8 bytes (game) + 4 bytes (move) + 32 bytes (salt) = 44 bytes total
+    const data = Buffer.concat([gameBuffer, moveBuffer, salt]);
     
     // Hash with SHA256
     const hash = crypto.createHash('sha256').update(data).digest();
@@ -45,7 +63,7 @@ function generateCommitment(moveId) {
         commitment: hash.toString('hex'),
         salt: salt.toString('hex'),
         moveId: moveId,
-        moveName: getMoveN(moveId)
+        moveName: getMoveName(moveId)
     };
 }
 
@@ -53,8 +71,7 @@ function generateCommitment(moveId) {
  * Get human-readable move name
  */
 function getMoveName(moveId) {
-    const moves = { 1: 'Attack', 2: 'Defense', 3: 'Magic' };
-    return moves[moveId] || 'Unknown';
+    return MOVE_NAMES[moveId] || 'Unknown';
 }
 
 // ============================================================================
@@ -64,25 +81,37 @@ function getMoveName(moveId) {
 if (require.main === module) {
     const args = process.argv.slice(2);
     
-    if (args.length === 0) {
+    // Optional --ruleset flag; everything else is positional
+    let ruleset = 'classic';
+    const rulesetIndex = args.indexOf('--ruleset');
+    if (rulesetIndex !== -1) {
+        ruleset = (args[rulesetIndex + 1] || '').toLowerCase();
+        args.splice(rulesetIndex, 2);
+    }
+    
+    if (args.length < 2) {
         console.log('📜 Stellar Duels - Commitment Generator');
         console.log('');
-        console.log('Usage: node generate_commitment.js <move_id>');
+        console.log('Usage: node generate_commitment.js <game_id> <move_id> [--ruleset classic|extended]');
         console.log('');
         console.log('Moves:');
-        console.log('  1 = Attack   (beats Defense)');
-        console.log('  2 = Defense  (beats Magic)');
-        console.log('  3 = Magic    (beats Attack)');
+        console.log('  1 = Attack   (beats Defense, Lizard)');
+        console.log('  2 = Defense  (beats Magic, Lizard)');
+        console.log('  3 = Magic    (beats Attack, Spock)');
+        console.log('  4 = Lizard   (beats Magic, Spock)     extended only');
+        console.log('  5 = Spock    (beats Attack, Defense)  extended only');
         console.log('');
         console.log('Example:');
-        console.log('  node generate_commitment.js 1');
+        console.log('  node generate_commitment.js 7 1');
+        console.log('  node generate_commitment.js 7 4 --ruleset extended');
         process.exit(0);
     }
     
-    const moveId = parseInt(args[0]);
+    const gameId = parseInt(args[0]);
+    const moveId = parseInt(args[1]);
     
     try {
-        const result = generateCommitment(moveId);
+        const result = generateCommitment(gameId, moveId, ruleset);
         
         console.log('');
         console.log('🎲 Move Commitment Generated');
@@ -104,10 +133,10 @@ if (require.main === module) {
         console.log('   3. Use the SALT when calling reveal_move()');
         console.log('');
         console.log('📋 Submit commitment with:');
-        console.log(`   stellar contract invoke --id <CONTRACT_ID> --source <PLAYER> --network testnet -- commit_move --game_id <ID> --player <PLAYER> --commitment ${result.commitment}`);
+        console.log(`   stellar contract invoke --id <CONTRACT_ID> --source <PLAYER> --network testnet -- commit_move --game_id ${gameId} --player <PLAYER> --commitment ${result.commitment}`);
         console.log('');
         console.log('🔓 Reveal later with:');
-        console.log(`   stellar contract invoke --id <CONTRACT_ID> --source <PLAYER> --network testnet -- reveal_move --game_id <ID> --player <PLAYER> --move_choice ${result.moveId} --salt ${result.salt}`);
+        console.log(`   stellar contract invoke --id <CONTRACT_ID> --source <PLAYER> --network testnet -- reveal_move --game_id ${gameId} --player <PLAYER> --move_choice ${result.moveId} --salt ${result.salt}`);
         console.log('');
         
     } catch (error) {
@@ -1,11 +1,16 @@
 const crypto = require('crypto');
 
+// Commitments are bound to a game: pass its ID as the first argument
+const gameId = BigInt(process.argv[2] || 1);
+const gameBuffer = Buffer.allocUnsafe(8);
+gameBuffer.writeBigUInt64BE(gameId);
+
 // Generate commitment for move 1 (Attack)
 const move1 = 1;
 const salt1 = crypto.randomBytes(32);
 const moveBuffer1 = Buffer.allocUnsafe(4);
 moveBuffer1.writeUInt32BE(move1);
-const data1 = Buffer.concat([moveBuffer1, salt1]);
+const data1 = Buffer.concat([gameBuffer, moveBuffer1, salt1]);
 const commitment1 = crypto.createHash('sha256').update(data1).digest('hex');
 
 // Generate commitment for move 3 (Magic)
@@ -13,7 +18,7 @@ const move2 = 3;
 const salt2 = crypto.randomBytes(32);
 const moveBuffer2 = Buffer.allocUnsafe(4);
 moveBuffer2.writeUInt32BE(move2);
-const data2 = Buffer.concat([moveBuffer2, salt2]);
+const data2 = Buffer.concat([gameBuffer, moveBuffer2, salt2]);
 const commitment2 = crypto.createHash('sha256').update(data2).digest('hex');
 
 console.log(JSON.stringify({
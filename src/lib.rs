@@ -15,27 +15,105 @@
 // Import the Soroban SDK - this is the foundation for all contract development
 use soroban_sdk::{
     contract,      // Macro to define the contract
+    contracterror, // Macro to define typed errors returned to clients
     contractimpl,  // Macro to define contract methods
     contracttype,  // Macro to define types that can be stored
+    panic_with_error, // Abort with a typed Error instead of a string message
+    symbol_short,  // Compile-time short symbols for event topics
     Address,       // Stellar address type (identifies accounts/contracts)
     BytesN,        // Fixed-size byte array (for hashes)
     Env,           // Environment - provides access to blockchain state, crypto, etc.
+    String,        // UTF-8 text (player names)
+    Symbol,        // Short string identifiers (event topics)
     Vec,           // Dynamic array
     token,         // Token interface for XLM transfers
 };
 
+// ============================================================================
+// ERRORS
+// ============================================================================
+
+/// Typed errors surfaced to clients (readable via the `try_` client methods)
+#[contracterror]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+#[repr(u32)]
+pub enum Error {
+    InvalidRoundCount = 1,  // Best-of-N round count is even, zero, or above MAX_ROUNDS
+    GameNotCompleted = 2,   // Operation needs a finished game
+    InvalidGameState = 3,   // Game is not in a state that allows this operation
+    OpponentNotCommitted = 4, // Only one player has committed; wait for the other
+    LobbyFull = 5,          // The global cap on concurrent active games is reached
+    RevealOutOfOrder = 6,   // This game requires player one to reveal first
+    ScoutNotPurchased = 7,  // Scout report read without buying a scout this round
+    SaltReused = 8,         // Salt is in the player's recent reveal history
+    InvalidStake = 9,       // Stake must be positive and its pot must fit MAX_POT
+    BetsFull = 10,          // The game holds the maximum number of bet entries
+    StakeOutOfBounds = 11,  // Stake is outside the admin-configured [min, max] range
+    Paused = 12,            // New games are paused by the admin
+    IncompatibleOptions = 13, // GameConfig combines options that can't be used together
+    TokenNotAllowed = 14,   // The token is not on the admin's whitelist
+    GameNotFound = 15,      // No game is stored under this ID
+    NotAuthorized = 16,     // Caller is not a player of this game
+    InvalidMove = 17,       // Move is outside 1..=move_count
+    AlreadyCommitted = 18,  // The player's commitment for this round is already in
+    CommitmentMismatch = 19, // Revealed move and salt don't hash to the commitment
+    PlayerNotRegistered = 20, // The address has no Player profile yet
+    TokenMismatch = 21,     // Token argument differs from the game's token
+    InvalidPenalty = 22,    // Reveal penalty above BPS_DENOMINATOR
+    WeakSalt = 23,          // An all-zero salt makes the commitment trivial to brute-force
+    DuplicateCommitment = 24, // Commitment is byte-for-byte the opponent's
+    AlreadyInitialized = 25, // initialize was already called
+    NotInitialized = 26,    // No admin stored yet; call initialize first
+    InvalidArgument = 27,   // A parameter is outside its allowed range
+    InvalidAmount = 28,     // Amount must be positive, fit its total, and balance out
+    InsufficientFunds = 29, // Balance or allowance doesn't cover the amount
+    AlreadyJoined = 30,     // The seat (or tournament entry) is already taken
+    SelfPlay = 31,          // Both sides of the action are the same address
+    InviteOnly = 32,        // The game is reserved for another invited player
+    NoOpponent = 33,        // The game has no second player
+    NotRevealed = 34,       // A move still has to be revealed
+    AlreadyRevealed = 35,   // The player's move for this round is already revealed
+    NotCommitted = 36,      // The player has no commitment to reveal or replace
+    EmptyCommitment = 37,   // An all-zero commitment is not a commitment
+    DeadlineNotReached = 38, // The relevant deadline or timelock hasn't passed
+    TimeoutNotAvailable = 39, // This game doesn't settle through this timeout path
+    ScoutUnavailable = 40,  // Commit status is public or was already scouted
+    ContinuationOffered = 41, // A continuation is already on the table
+    NoContinuationOffered = 42, // Nothing to accept
+    BettingClosed = 43,     // Bets only while both moves are sealed and unrevealed
+    NoBets = 44,            // The game has no bets to cancel or settle
+    DisputeExists = 45,     // The game is already flagged
+    DisputeNotFound = 46,   // No dispute is stored for this game
+    DisputeResolved = 47,   // The dispute already has a resolution
+    TournamentNotFound = 48, // No tournament is stored under this ID
+    InvalidTournamentState = 49, // The tournament is not in a state that allows this
+    AlreadyPlayed = 50,     // Only a player's first game can be sponsored
+}
+
 // ============================================================================
 // DATA STRUCTURES
 // ============================================================================
 
 /// Represents a player's move in the game
 /// Similar to Rock-Paper-Scissors but themed for combat
+/// Lizard and Spock are only valid once the contract is configured for 5 moves
 #[contracttype]
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Move {
-    Attack = 1,   // Beats Defense (overpowers shield)
-    Defense = 2,  // Beats Magic (blocks spell)
-    Magic = 3,    // Beats Attack (confuses warrior)
+    Attack = 1,   // Beats Defense (overpowers shield) and Lizard
+    Defense = 2,  // Beats Magic (blocks spell) and Lizard
+    Magic = 3,    // Beats Attack (confuses warrior) and Spock
+    Lizard = 4,   // Beats Magic and Spock
+    Spock = 5,    // Beats Defense and Attack
+}
+
+/// Move set a game is played with
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Ruleset {
+    House = 0,    // Whatever the admin configured with set_move_count (default)
+    Classic = 1,  // Attack, Defense, Magic
+    Extended = 2, // Adds Lizard and Spock
 }
 
 /// Tracks the current state of a game
@@ -47,15 +125,99 @@ pub enum GameState {
     Completed,         // Game finished, winner determined
 }
 
+/// How a completed game was resolved
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GameOutcome {
+    Pending = 0, // Not resolved yet
+    Won = 1,    // A player won the match on moves
+    Draw = 2,   // Single-shot game drawn, stakes refunded
+    Arbitrated = 3, // Settled by the admin through resolve_dispute
+    Merged = 4,     // Closed by match_open_games; the stake moved to the other game
+    TimedOut = 5,   // A player missed a deadline; settled by a timeout claim
+    Cancelled = 6,  // Withdrawn by its creator before anyone joined
+    Expired = 7,    // Left open past OPEN_GAME_EXPIRY and closed by expire_open_game
+    Forfeited = 8,  // A player conceded; the opponent took the pot
+    Swept = 9,      // Abandoned past ABANDONED_GAME_EXPIRY; pot moved to the treasury
+}
+
+/// Admin decision on a flagged dispute
+/// Dismissed/Upheld only record the decision; the others settle a stuck game
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisputeResolution {
+    Unresolved = 0,     // Still awaiting the admin
+    Dismissed = 1,      // No action warranted
+    Upheld = 2,         // Complaint valid; remedy happens off-chain
+    RefundBoth = 3,     // Stuck game: return each deposited stake
+    AwardPlayerOne = 4, // Stuck game: whole pot to player one
+    AwardPlayerTwo = 5, // Stuck game: whole pot to player two
+}
+
+/// How a single-shot game that ends in a draw is settled
+/// Drawn rounds of a best-of-N match are always replayed instead
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawPolicy {
+    Refund = 0,       // Return both stakes (default)
+    HashTiebreak = 1, // Pick a winner from the revealed commitments, see tiebreak_by_hash
+    DonateToPool = 2, // Add the whole pot to the token's rewards pool
+    SuddenDeath = 3,  // Replay the round for the same pot until someone wins
+    SplitPot = 4,     // Halve the pot, player one taking any odd stroop
+    SuddenDeathRandom = 5, // Coin flip for the whole pot, see tiebreak_by_ledger
+}
+
+/// How draws count towards ratings and leaderboard standings
+/// The raw draws counters are kept the same way under every option.
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DrawScoring {
+    CountSeparately = 0, // Rank on wins alone; a draw rates as half a point (default)
+    HalfWin = 1,         // Rank on half-points (2 per win, 1 per draw); rate as above
+    Ignore = 2,          // Rank on wins alone and leave ratings untouched on a draw
+}
+
+/// Lifecycle of a single-elimination tournament
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TournamentState {
+    Registration = 0, // Collecting entries until the bracket is full
+    Running = 1,      // Bracket games are being played
+    Finished = 2,     // A champion was paid the prize pool
+    Cancelled = 3,    // Called off by the organizer; entries refunded
+}
+
+/// Why an active game needs an operator's (or anyone's) attention
+#[contracttype]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StuckReason {
+    ReadyToFinalize = 1, // Both moves revealed but nobody has finalized
+    RevealOverdue = 2,   // The reveal window closed with a reveal still missing
+    Disputed = 3,        // A participant flagged a dispute awaiting the admin
+    CommitOverdue = 4,   // The commit window closed with a commitment still missing
+}
+
+/// A participant's request for off-chain arbitration of a game
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Dispute {
+    pub game_id: u64,
+    pub flagged_by: Address,
+    pub reason_code: u32,              // Client-defined reason (opaque to the contract)
+    pub flagged_ledger: u32,
+    pub resolution: DisputeResolution,
+}
+
 /// Complete game data structure
 /// This is stored on-chain for each active game
 #[contracttype]
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct Game {
     pub game_id: u64,              // Unique game identifier
     pub player_one: Address,        // First player's address
     pub player_two: Option<Address>, // Second player (None until someone joins)
-    pub stake_amount: i128,         // XLM tokens staked per player
+    pub stake_amount: i128,         // Stake of player one (and of player two unless handicapped)
+    pub p2_stake: i128,             // Stake player two pays to join
     pub state: GameState,           // Current game state
     
     // Commit-reveal mechanism - using empty bytes to represent "None"
@@ -65,7 +227,261 @@ pub struct Game {
     pub p1_move: u32,      // Player 1's revealed move (0 = not revealed, 1-3 = move)
     pub p2_move: u32,      // Player 2's revealed move (0 = not revealed, 1-3 = move)
     
+    // Revealed salts, kept so the round can be proven fair afterwards
+    pub p1_salt: BytesN<32>, // Player 1's salt this round (all zeros = not revealed)
+    pub p2_salt: BytesN<32>, // Player 2's salt this round (all zeros = not revealed)
+    
     pub winner: Option<Address>,    // Winner's address (None = draw or incomplete)
+    pub token: Address,             // Token the stakes are denominated in
+    
+    // Best-of-N match tracking
+    pub rounds: u32,                // Total rounds in the match (odd, 1 = single-shot)
+    pub current_round: u32,         // Round currently being played (starts at 1)
+    pub p1_round_wins: u32,         // Rounds won by player 1
+    pub p2_round_wins: u32,         // Rounds won by player 2
+    
+    // Auto-rematch chaining
+    pub settle_internal: bool,      // Stakes came from internal balances; payouts go back there
+    pub next_game_id: Option<u64>,  // Game auto-created as this one's rematch, if any
+    
+    pub hide_progress: bool,        // Hide who has committed until both have
+    pub move_count: u32,            // Valid moves are 1..=move_count (fixed at creation)
+    pub outcome: GameOutcome,       // How the game was resolved (Pending until completed)
+    
+    // Commit and reveal phase timing
+    pub commit_window: u32,         // Ledgers allowed for commits once seated (0 = none)
+    pub commit_deadline: u32,       // Absolute ledger the current commit window ends at
+    pub reveal_window: u32,         // Ledgers allowed for reveals once both commit (0 = none)
+    pub reveal_deadline: u32,       // Absolute ledger the current reveal window ends at
+    
+    // Comment moderation: clients hide this game's comments when set
+    pub moderated: bool,
+    
+    // Draw handling
+    pub draw_policy: DrawPolicy,
+    pub tiebreak_seed: BytesN<32>,  // XOR of the salts revealed this round
+    
+    // Player one must reveal before player two when set
+    pub reveal_order_enforced: bool,
+    
+    // Pay-as-you-go stakes: each round's stake is pulled via allowance at
+    // round start instead of the whole stake up front
+    pub round_stakes: bool,
+    pub pot: i128,                  // Stakes pulled so far (round_stakes games only)
+    
+    pub timings: PhaseTimings,      // Ledgers of each phase transition
+    pub sponsor: Option<Address>,   // Who onboarded the creator (never owns the stake)
+    pub invited: Option<Address>,   // The only address allowed to join (None = open game)
+    pub continuation_offered: bool, // The round's winner offered double-or-nothing
+    pub reveal_penalty_bps: u32,    // No-show's stake share a lone revealer gets (0 = whole pot)
+}
+
+/// Everything a creator chooses when opening a game (see create_game)
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameConfig {
+    pub stake_amount: i128,         // Stake each player puts up (per round with round_stakes)
+    pub token: Address,             // Token the stakes are denominated in
+    /// Best-of-N round count (odd, 1..=MAX_ROUNDS; 1 = single-shot)
+    pub rounds: u32,
+    /// Hide each player's commit status from the other until both have
+    /// committed (see get_game_progress)
+    pub hide_progress: bool,
+    /// Ledgers players have to commit once both are seated, restarting each
+    /// round (0 = no deadline; see claim_commit_timeout)
+    pub commit_window: u32,
+    /// Ledgers players have to reveal once both moves are committed (0 = no deadline)
+    pub reveal_window: u32,
+    /// How a drawn single-shot game is settled; the joiner accepts it by
+    /// joining, which is how DonateToPool gets both players' consent
+    pub draw_policy: DrawPolicy,
+    /// Make player two wait for player one's reveal, removing the second
+    /// revealer's choice to reveal or walk away
+    pub reveal_order_enforced: bool,
+    /// Charge `stake_amount` per round from each player's token allowance to
+    /// this contract instead of locking it all at creation; a player whose
+    /// allowance or balance falls short forfeits that round
+    pub round_stakes: bool,
+    /// Move set; the resolved count is stored as the game's move_count
+    pub ruleset: Ruleset,
+    /// Reserve the second seat for this address (None = anyone may join)
+    pub invited: Option<Address>,
+    /// Take both stakes from internal balances (see deposit) instead of
+    /// wallet transfers; payouts are credited back to those balances
+    pub use_balance: bool,
+    /// Handicap: what player two stakes instead of `stake_amount`, e.g. a
+    /// mentor putting up more than a beginner (None = the same stake)
+    pub p2_stake: Option<i128>,
+    /// Soften reveal timeouts: the player who revealed gets their stake back
+    /// plus this share (bps) of the no-show's stake, the rest going back to
+    /// the no-show (see claim_reveal_timeout_partial). 0 = winner takes all
+    pub reveal_penalty_bps: u32,
+}
+
+impl GameConfig {
+    /// A single-shot classic game: no deadlines, draws refunded
+    pub fn new(stake_amount: i128, token: &Address) -> Self {
+        GameConfig {
+            stake_amount,
+            token: token.clone(),
+            rounds: 1,
+            hide_progress: false,
+            commit_window: 0,
+            reveal_window: 0,
+            draw_policy: DrawPolicy::Refund,
+            reveal_order_enforced: false,
+            round_stakes: false,
+            ruleset: Ruleset::House,
+            invited: None,
+            use_balance: false,
+            p2_stake: None,
+            reveal_penalty_bps: 0,
+        }
+    }
+}
+
+/// Scoreboard of a best-of-N match (single-shot games are best-of-1)
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MatchStatus {
+    pub current_round: u32,         // Round being played, counting replayed draws
+    pub rounds: u32,                // Best-of-N round count
+    pub p1_round_wins: u32,
+    pub p2_round_wins: u32,
+    pub wins_needed: u32,           // Round wins that clinch the match
+}
+
+/// Ledger sequence at which a game entered each phase (0 = not reached yet)
+/// In multi-round matches the commit and reveal ledgers track the latest round.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PhaseTimings {
+    pub created: u32,
+    pub joined: u32,
+    pub p1_committed: u32,          // Player one's commitment arrived
+    pub p2_committed: u32,          // Player two's commitment arrived
+    pub committed: u32,             // Both moves sealed
+    pub p1_revealed: u32,           // Player one's move was revealed
+    pub p2_revealed: u32,           // Player two's move was revealed
+    pub completed: u32,
+}
+
+/// One resolved round of a match, kept for replays
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundRecord {
+    pub round: u32,
+    pub p1_move: u32,
+    pub p2_move: u32,
+    pub p1_commitment: BytesN<32>,
+    pub p2_commitment: BytesN<32>,
+    pub p1_salt: BytesN<32>,
+    pub p2_salt: BytesN<32>,
+    pub winner: Option<Address>,   // None = drawn round
+    pub ledger: u32,               // Ledger sequence the round was resolved in
+    pub timestamp: u64,            // Ledger close time the round was resolved at
+}
+
+/// One round's commit-reveal evidence, see export_fairness_proof
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RoundProof {
+    pub round: u32,
+    pub p1_commitment: BytesN<32>, // Stored when player one committed
+    pub p2_commitment: BytesN<32>, // Stored when player two committed
+    pub p1_move: u32,
+    pub p2_move: u32,
+    pub p1_salt: BytesN<32>,
+    pub p2_salt: BytesN<32>,
+    pub p1_recomputed: BytesN<32>, // calculate_commitment over the revealed move and salt
+    pub p2_recomputed: BytesN<32>,
+    pub winner: Option<Address>,   // Round winner by the move rules (None = drawn)
+}
+
+/// Self-contained evidence that a finished game was decided honestly
+/// A verifier recomputes each commitment as SHA256(game_id || move || salt),
+/// compares it with the stored one and re-applies the move rules.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FairnessProof {
+    pub game_id: u64,
+    pub player_one: Address,
+    pub player_two: Address,
+    pub move_count: u32,           // Move set the rules are applied over
+    pub draw_policy: DrawPolicy,   // How a drawn single-shot game was settled
+    pub outcome: GameOutcome,
+    pub winner: Option<Address>,
+    pub rounds: Vec<RoundProof>,   // Every resolved round, in play order
+}
+
+/// Everything a replay viewer needs to animate a finished match
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub game_id: u64,
+    pub player_one: Address,
+    pub player_two: Address,
+    pub token: Address,
+    pub stake_amount: i128,
+    pub rounds: u32,
+    pub p1_round_wins: u32,
+    pub p2_round_wins: u32,
+    pub winner: Option<Address>,
+    pub history: Vec<RoundRecord>, // Every resolved round, in play order
+}
+
+/// Compact record of how a game was settled, kept apart from the Game entry
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameResult {
+    pub game_id: u64,
+    pub player_one: Address,
+    pub player_two: Address,
+    pub p1_move: u32,               // Deciding round's moves (0 = never revealed)
+    pub p2_move: u32,
+    pub winner: Option<Address>,    // None for draws and refunds
+    pub pot: i128,                  // Total stakes settled
+    pub finalized_ledger: u32,
+}
+
+/// A single-elimination bracket funded by equal entry stakes
+/// Bracket games are played for no stake of their own; the whole pool goes
+/// to the champion, less the token's house fee.
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Tournament {
+    pub tournament_id: u64,
+    pub organizer: Address,
+    pub token: Address,
+    pub entry_stake: i128,
+    pub max_players: u32,           // Bracket size (a power of two)
+    pub players: Vec<Address>,      // Entrants, in seeding (join) order
+    pub state: TournamentState,
+    pub round: u32,                 // Current bracket round (0 before the start)
+    pub games: Vec<u64>,            // This round's game IDs, in bracket order
+    pub champion: Option<Address>,
+    pub commit_window: u32,         // Commit window of every bracket game
+    pub reveal_window: u32,         // Reveal window of every bracket game
+}
+
+/// A spectator's wager on one of a game's players
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Bet {
+    pub bettor: Address,
+    pub on_player: Address,
+    pub amount: i128,
+}
+
+/// Commit/reveal progress of a game's current round, as seen by one viewer
+#[contracttype]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GameProgress {
+    pub round: u32,
+    pub p1_committed: bool,
+    pub p2_committed: bool,
+    pub p1_revealed: bool,
+    pub p2_revealed: bool,
 }
 
 /// Player profile stored on-chain
@@ -76,6 +492,24 @@ pub struct Player {
     pub wins: u32,
     pub losses: u32,
     pub draws: u32,
+    pub notification_prefs: u32,   // Bitmask of NOTIFY_* flags read by frontends/keepers
+    pub rating: u32,               // Skill rating, starts at DEFAULT_RATING
+    pub name: String,              // Display handle, at most MAX_NAME_LEN bytes (empty by default)
+    pub move_counts: Vec<u32>,     // Reveals per move; entry i counts move i + 1 (Attack first)
+    pub current_win_streak: u32,   // Consecutive wins up to the latest game
+    pub best_win_streak: u32,      // Longest win streak ever reached
+    pub total_staked: i128,        // Sum of every game stake deposited, refunds included
+    pub total_won: i128,           // Sum of pots paid out for wins, after the house fee
+}
+
+/// A player's results within one season; the current season's wins are
+/// what the leaderboard ranks (Player's wins/losses/draws stay lifetime)
+#[contracttype]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SeasonStats {
+    pub wins: u32,
+    pub losses: u32,
+    pub draws: u32,
 }
 
 // ============================================================================
@@ -91,8 +525,162 @@ pub enum DataKey {
     Game(u64),             // Stores Game struct by game_id
     Player(Address),       // Stores Player struct by address
     ActiveGames,           // Stores Vec<u64> of active game IDs
+    Admin,                 // Stores the admin Address (set once by initialize)
+    FeeBps,                // Stores the global house fee in basis points
+    TokenFeeBps(Address),  // Stores a per-token fee override in basis points
+    Treasury(Address),     // Stores collected fees per token (held by the contract)
+    Balance(Address, Address),     // Internal balance by (player, token)
+    AutoRematch(Address, Address), // Remaining auto-rematches by (player, opponent)
+    BalanceTotal(Address),         // Sum of all internal balances per token
+    RoundHistory(u64),             // Stores Vec<RoundRecord> for a game
+    Leaderboard,                   // Stores top players as Vec<(Address, u32 season wins)>
+    MoveCount,                     // Stores the configured number of valid moves
+    RewardsPool(Address),          // Admin-funded completion bonuses available per token
+    RewardBps,                     // Completion bonus per participant, in bps of stake
+    MaxActiveGames,                // Stores the cap on concurrent active games
+    Dispute(u64),                  // Stores the Dispute flagged on a game
+    Bets(u64),                     // Stores Vec<Bet> of unsettled spectator bets
+    BetTotals(u64),                // Stores (on player_one, on player_two) wagered totals
+    BetEscrow(Address),            // Sum of all unsettled bets per token
+    RevealBonus,                   // Stores the early-reveal bonus paid at window start
+    Comments(u64),                 // Append-only comment hashes per game
+    WithdrawalTimelock,            // Stores ledgers winnings stay locked after crediting
+    LockedCredits(Address, Address), // Pending (amount, unlock ledger) credits per player/token
+    PlayerGames(Address),          // Most recent game IDs a player took part in
+    CurrentSeason,                 // Stores the running season number
+    SeasonLeaderboard(u32),        // Final leaderboard of a finished season
+    PlayerSeason(Address, u32),    // SeasonStats of a player in a season
+    Scouts(u64),                   // Scout purchases per game as Vec<(Address, u32 round)>
+    SaltHistoryLimit,              // Stores how many recent salts are remembered per player
+    RecentSalts(Address),          // Salts a player revealed most recently, oldest first
+    MovePerformance(Address),      // Stores Vec<(move, wins, losses)> of decided rounds
+    MaxBetsPerGame,                // Stores the cap on bet entries per game
+    StakeBounds,                   // Stores the (min, max) stake a game may use
+    Paused,                        // Stores whether new games are blocked
+    DrawsResetStreaks,             // Stores whether a draw ends a win streak
+    DrawScoring,                   // Stores how draws count for ratings and standings
+    GameResult(u64),               // Stores the GameResult of a settled game
+    TournamentCounter,             // Stores the next tournament ID
+    Tournament(u64),               // Stores Tournament struct by tournament_id
+    TournamentEscrow(Address),     // Sum of entry stakes held for tournaments per token
+    H2H(Address, Address),         // (lower wins, higher wins, draws) for an address pair, lower first
+    TokenWhitelist,                // Set once the admin first whitelists a token
+    AllowedToken(Address),         // Present for each whitelisted token
 }
 
+// ============================================================================
+// CONSTANTS
+// ============================================================================
+
+/// Basis-point denominator (10000 bps = 100%)
+const BPS_DENOMINATOR: i128 = 10_000;
+
+/// Largest pot a game may hold, leaving headroom for bps arithmetic on it
+const MAX_POT: i128 = i128::MAX / BPS_DENOMINATOR;
+
+/// Upper bound on any fee rate the admin can configure (1000 bps = 10%)
+const MAX_FEE_BPS: u32 = 1_000;
+
+/// Upper bound on the completion bonus rate (1000 bps = 10% of stake)
+const MAX_REWARD_BPS: u32 = 1_000;
+
+/// Largest tournament bracket (bounds the games created per round)
+const MAX_TOURNAMENT_PLAYERS: u32 = 32;
+
+/// Upper bound on best-of-N round counts (bounds storage and gas per match)
+const MAX_ROUNDS: u32 = 99;
+
+/// Auto-rematches granted per opt-in; players re-confirm once it runs out
+const AUTO_REMATCH_LIMIT: u32 = 10;
+
+/// Move set used when the admin hasn't configured one (Attack/Defense/Magic)
+const DEFAULT_MOVE_COUNT: u32 = 3;
+
+/// Extended move set adding Lizard and Spock
+const EXTENDED_MOVE_COUNT: u32 = 5;
+
+/// Cap on concurrent active games when the admin hasn't set one
+const DEFAULT_MAX_ACTIVE_GAMES: u32 = 10_000;
+
+/// First topic of every event this contract publishes; the second topic names
+/// the transition (created, joined, committed, revealed, finalized, ...)
+const EVENT_TOPIC: Symbol = symbol_short!("duel");
+
+/// Cap on bet entries per game when the admin hasn't set one
+/// settle_bets pays every entry in one call, so this bounds its cost.
+const DEFAULT_MAX_BETS_PER_GAME: u32 = 100;
+
+/// Number of players kept on the leaderboard
+/// Each win costs at most one O(N) scan and insert, so N stays small
+const LEADERBOARD_SIZE: u32 = 50;
+
+/// Game IDs kept per player in the history index (oldest are dropped)
+const PLAYER_GAMES_LIMIT: u32 = 100;
+
+/// Active games quick_match inspects when looking for an opponent
+const QUICK_MATCH_SCAN: u32 = 50;
+
+/// Price of a scout, as a share of the game's stake (1000 bps = 10%)
+const SCOUT_COST_BPS: i128 = 1_000;
+
+/// Active games get_stuck_games inspects per call
+const MAINTENANCE_SCAN: u32 = 100;
+
+/// Storage TTLs for live games, in ledgers (~5s each)
+/// Any move on a game tops it, the active list, its players and the contract
+/// instance up to GAME_TTL_EXTEND_TO (~30 days) once they fall below
+/// GAME_TTL_THRESHOLD (~1 day), so an active game is never archived mid-match.
+const GAME_TTL_THRESHOLD: u32 = 17_280;
+const GAME_TTL_EXTEND_TO: u32 = 518_400;
+
+/// Upper bound on the per-player salt history (bounds storage per player)
+const MAX_SALT_HISTORY: u32 = 50;
+
+/// Rating every newly registered player starts at
+const DEFAULT_RATING: u32 = 1200;
+
+/// Elo K-factor: the most a rating can move in one game
+const ELO_K_FACTOR: i128 = 32;
+
+/// Elo expected score (bps) for rating leads of 0, 25, 50, ... 800 points
+/// Values in between are interpolated; larger leads use the last entry
+const ELO_EXPECTED_BPS: [i128; 33] = [
+    5000, 5359, 5715, 6063, 6401, 6725, 7034, 7325, 7597, 7850, 8083,
+    8296, 8490, 8666, 8823, 8965, 9091, 9203, 9302, 9390, 9468, 9536,
+    9595, 9648, 9693, 9733, 9768, 9799, 9825, 9848, 9868, 9886, 9901,
+];
+
+/// Ledgers an unjoined game stays open before anyone may expire it (~7 days)
+const OPEN_GAME_EXPIRY: u32 = 120_960;
+
+/// Ledgers without any activity before the admin may sweep a seated game's
+/// pot into the treasury (~58 days)
+const ABANDONED_GAME_EXPIRY: u32 = 1_000_000;
+
+/// Longest commit and reveal windows a game can ask for (~7 days each),
+/// keeping every deadline well inside the ledger sequence range
+const MAX_COMMIT_WINDOW: u32 = 120_960;
+const MAX_REVEAL_WINDOW: u32 = 120_960;
+
+/// Commit and reveal windows given to tournament bracket games (~1 day
+/// each), so a player who stops showing up can be timed out of the bracket
+const BRACKET_COMMIT_WINDOW: u32 = 17_280;
+const BRACKET_REVEAL_WINDOW: u32 = 17_280;
+
+/// Longest lock the admin can put on internally credited winnings (~30 days)
+const MAX_WITHDRAWAL_TIMELOCK: u32 = 518_400;
+
+/// Longest display name a player can set, in bytes
+const MAX_NAME_LEN: u32 = 32;
+
+/// Notification preference bits stored on the Player profile
+/// The contract only stores them; off-chain services decide what to send
+pub const NOTIFY_OPPONENT_COMMITTED: u32 = 1 << 0; // Opponent submitted a commitment
+pub const NOTIFY_REVEAL_TURN: u32 = 1 << 1;        // Both committed, time to reveal
+pub const NOTIFY_GAME_FINALIZED: u32 = 1 << 2;     // A game you played was settled
+pub const NOTIFY_ALL: u32 =
+    NOTIFY_OPPONENT_COMMITTED | NOTIFY_REVEAL_TURN | NOTIFY_GAME_FINALIZED;
+
 // ============================================================================
 // SMART CONTRACT IMPLEMENTATION
 // ============================================================================
@@ -104,480 +692,9048 @@ pub struct StellarDuelsContract;
 impl StellarDuelsContract {
     
     // ========================================================================
-    // PLAYER MANAGEMENT
+    // ADMIN & FEE CONFIGURATION
     // ========================================================================
     
-    /// Register a new player or retrieve existing player data
-    /// This function is idempotent - calling it multiple times is safe
-    pub fn register_player(env: Env, player: Address) -> Player {
-        // Verify that the caller is authorized to register this address
-        // This prevents someone from registering another person's address
-        player.require_auth();
-        
-        let key = DataKey::Player(player.clone());
+    /// One-time setup: store the admin and the global house fee
+    /// The fee is only taken from pots that produce a winner (never from draws)
+    pub fn initialize(env: Env, admin: Address, fee_bps: u32) {
+        admin.require_auth();
         
-        // Check if player already exists in storage
-        if let Some(existing_player) = env.storage().persistent().get::<DataKey, Player>(&key) {
-            return existing_player;
+        if env.storage().persistent().has(&DataKey::Admin) {
+            panic_with_error!(&env, Error::AlreadyInitialized);
+        }
+        if fee_bps > MAX_FEE_BPS {
+            panic_with_error!(&env, Error::InvalidArgument);
         }
         
-        // Create new player profile
-        let new_player = Player {
-            address: player.clone(),
-            wins: 0,
-            losses: 0,
-            draws: 0,
-        };
-        
-        // Store in persistent storage (survives contract upgrades)
-        env.storage().persistent().set(&key, &new_player);
-        
-        new_player
+        env.storage().persistent().set(&DataKey::Admin, &admin);
+        env.storage().persistent().set(&DataKey::FeeBps, &fee_bps);
     }
     
-    /// Retrieve player statistics
-    pub fn get_player(env: Env, player: Address) -> Option<Player> {
-        let key = DataKey::Player(player);
-        env.storage().persistent().get(&key)
+    /// Retrieve the admin address (None before initialize)
+    pub fn get_admin(env: Env) -> Option<Address> {
+        env.storage().persistent().get(&DataKey::Admin)
     }
     
-    // ========================================================================
-    // GAME MANAGEMENT
-    // ========================================================================
+    /// Retrieve the global fee rate in basis points (0 if never configured)
+    pub fn get_fee_bps(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::FeeBps).unwrap_or(0)
+    }
     
-    /// Create a new game with a stake amount
-    /// The creator becomes player_one and must deposit stake_amount XLM
-    pub fn create_game(
-        env: Env,
-        creator: Address,
-        stake_amount: i128,
-        token_address: Address,
-    ) -> u64 {
-        // Verify the creator authorized this action
-        creator.require_auth();
-        
-        // Ensure player is registered
-        assert!(
-            env.storage().persistent().has(&DataKey::Player(creator.clone())),
-            "Player must be registered first"
-        );
-        
-        // Get next game ID (auto-increment counter)
-        let game_id = Self::get_and_increment_counter(&env);
-        
-        // Transfer stake from creator to contract
-        // This locks the funds until the game completes
-        let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(
-            &creator,
-            &env.current_contract_address(),
-            &stake_amount,
-        );
-        
-        // Create game data structure
-        let game = Game {
-            game_id,
-            player_one: creator.clone(),
-            player_two: None,
-            stake_amount,
-            state: GameState::WaitingForPlayer,
-            p1_commitment: BytesN::from_array(&env, &[0u8; 32]),
-            p2_commitment: BytesN::from_array(&env, &[0u8; 32]),
-            p1_move: 0,
-            p2_move: 0,
-            winner: None,
-        };
-        
-        // Store game in persistent storage
-        env.storage().persistent().set(&DataKey::Game(game_id), &game);
-        
-        // Add to active games list
-        Self::add_to_active_games(&env, game_id);
+    /// Update the global fee rate (admin only)
+    pub fn set_fee_bps(env: Env, fee_bps: u32) {
+        Self::require_admin(&env);
+        if fee_bps > MAX_FEE_BPS {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
         
-        game_id
+        env.storage().persistent().set(&DataKey::FeeBps, &fee_bps);
     }
     
-    /// Join an existing game as player_two
-    /// Must deposit the same stake_amount as player_one
-    pub fn join_game(
-        env: Env,
-        game_id: u64,
-        player: Address,
-        token_address: Address,
-    ) -> Game {
-        player.require_auth();
+    /// Override the fee rate for a specific staking token (admin only)
+    /// e.g. a partner token can be set to 0 bps while XLM keeps the global rate
+    pub fn set_fee_for_token(env: Env, token: Address, fee_bps: u32) {
+        Self::require_admin(&env);
+        if fee_bps > MAX_FEE_BPS {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
         
-        // Retrieve the game
-        let mut game: Game = env.storage()
+        env.storage().persistent().set(&DataKey::TokenFeeBps(token), &fee_bps);
+    }
+    
+    /// Effective fee rate for a token: its override if set, otherwise the global rate
+    pub fn get_fee_for_token(env: Env, token: Address) -> u32 {
+        env.storage()
             .persistent()
-            .get(&DataKey::Game(game_id))
-            .expect("Game not found");
-        
-        // Validate game state
-        assert_eq!(game.state, GameState::WaitingForPlayer, "Game is not accepting players");
-        assert!(game.player_two.is_none(), "Game already has two players");
-        assert!(player != game.player_one, "Cannot play against yourself");
-        
-        // Ensure player is registered
-        assert!(
-            env.storage().persistent().has(&DataKey::Player(player.clone())),
-            "Player must be registered first"
-        );
+            .get(&DataKey::TokenFeeBps(token))
+            .unwrap_or_else(|| Self::get_fee_bps(env.clone()))
+    }
+    
+    /// Add tokens to the rewards pool that pays completion bonuses (admin only)
+    pub fn fund_rewards(env: Env, amount: i128, token_address: Address) -> i128 {
+        let admin = Self::require_admin(&env);
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
         
-        // Transfer stake from joining player to contract
         let token_client = token::Client::new(&env, &token_address);
-        token_client.transfer(
-            &player,
-            &env.current_contract_address(),
-            &game.stake_amount,
-        );
+        token_client.transfer(&admin, &env.current_contract_address(), &amount);
         
-        // Update game with second player
-        game.player_two = Some(player);
+        let key = DataKey::RewardsPool(token_address);
+        let pool: i128 = env.storage().persistent().get(&key).unwrap_or(0) + amount;
+        env.storage().persistent().set(&key, &pool);
         
-        // Save updated game
-        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        pool
+    }
+    
+    /// Remaining rewards pool for a token
+    pub fn get_rewards_pool(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RewardsPool(token))
+            .unwrap_or(0)
+    }
+    
+    /// Set the completion bonus each participant earns, in bps of their stake
+    /// (admin only, 0 disables bonuses)
+    pub fn set_reward_bps(env: Env, reward_bps: u32) {
+        Self::require_admin(&env);
+        if reward_bps > MAX_REWARD_BPS {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
         
-        game
+        env.storage().persistent().set(&DataKey::RewardBps, &reward_bps);
     }
     
-    /// Retrieve game data
-    pub fn get_game(env: Env, game_id: u64) -> Option<Game> {
-        env.storage().persistent().get(&DataKey::Game(game_id))
+    /// Completion bonus rate in bps of stake (0 if never configured)
+    pub fn get_reward_bps(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::RewardBps).unwrap_or(0)
     }
     
-    /// List all active game IDs
-    pub fn get_active_games(env: Env) -> Vec<u64> {
+    /// Cap the number of concurrent active games (admin only)
+    /// create_game fails with Error::LobbyFull while the cap is reached
+    pub fn set_max_active_games(env: Env, max_active_games: u32) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(&DataKey::MaxActiveGames, &max_active_games);
+    }
+    
+    /// Limit the stake a new game may use, inclusive (admin only)
+    /// create_game fails with Error::StakeOutOfBounds outside the range
+    pub fn set_stake_bounds(env: Env, min_stake: i128, max_stake: i128) {
+        Self::require_admin(&env);
+        if min_stake <= 0 || min_stake > max_stake {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        env.storage().persistent().set(&DataKey::StakeBounds, &(min_stake, max_stake));
+    }
+    
+    /// Current (min, max) stake bounds; any positive stake until configured
+    pub fn get_stake_bounds(env: Env) -> (i128, i128) {
         env.storage()
             .persistent()
-            .get(&DataKey::ActiveGames)
-            .unwrap_or(Vec::new(&env))
+            .get(&DataKey::StakeBounds)
+            .unwrap_or((1, i128::MAX))
     }
     
-    // ========================================================================
-    // COMMIT-REVEAL MECHANISM
-    // ========================================================================
+    /// Stop (or resume) opening and joining games (admin only)
+    /// Games already under way can still be revealed, finalized, claimed
+    /// and withdrawn from while paused.
+    pub fn set_paused(env: Env, paused: bool) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(&DataKey::Paused, &paused);
+    }
     
-    /// Submit a move commitment (hash of move + secret salt)
-    /// This hides the move from the opponent until reveal phase
-    /// 
-    /// How to generate commitment off-chain:
-    /// 1. Choose your move (1=Attack, 2=Defense, 3=Magic)
-    /// 2. Generate random 32-byte salt
-    /// 3. commitment = SHA256(move_bytes || salt_bytes)
-    /// 4. Submit this commitment hash
-    pub fn commit_move(
-        env: Env,
-        game_id: u64,
-        player: Address,
-        commitment: BytesN<32>,
-    ) -> Game {
-        player.require_auth();
-        
-        let mut game: Game = env.storage()
-            .persistent()
-            .get(&DataKey::Game(game_id))
-            .expect("Game not found");
+    /// Whether new games are currently blocked
+    pub fn is_paused(env: Env) -> bool {
+        env.storage().persistent().get(&DataKey::Paused).unwrap_or(false)
+    }
+    
+    /// Whitelist a token for new games (admin only)
+    /// Until the first token is added every token is accepted; from then on
+    /// create_game fails with Error::TokenNotAllowed for tokens not listed.
+    pub fn add_allowed_token(env: Env, token: Address) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(&DataKey::TokenWhitelist, &true);
+        env.storage().persistent().set(&DataKey::AllowedToken(token), &true);
+    }
+    
+    /// Take a token off the whitelist (admin only)
+    /// Games already using it play out and settle normally.
+    pub fn remove_allowed_token(env: Env, token: Address) {
+        Self::require_admin(&env);
+        env.storage().persistent().remove(&DataKey::AllowedToken(token));
+    }
+    
+    /// Whether new games may be denominated in `token`
+    pub fn is_token_allowed(env: Env, token: Address) -> bool {
+        !env.storage().persistent().has(&DataKey::TokenWhitelist)
+            || env.storage().persistent().has(&DataKey::AllowedToken(token))
+    }
+    
+    /// Choose whether a draw ends a player's win streak (admin only)
+    /// By default draws leave streaks untouched.
+    pub fn set_draws_reset_streaks(env: Env, reset: bool) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(&DataKey::DrawsResetStreaks, &reset);
+    }
+    
+    /// Whether a draw currently ends a win streak
+    pub fn get_draws_reset_streaks(env: Env) -> bool {
+        env.storage().persistent().get(&DataKey::DrawsResetStreaks).unwrap_or(false)
+    }
+    
+    /// Choose how draws count for ratings and leaderboard standings (admin only)
+    /// Applies to results from now on; standings already recorded this
+    /// season aren't recomputed.
+    pub fn set_draw_scoring(env: Env, scoring: DrawScoring) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(&DataKey::DrawScoring, &scoring);
+    }
+    
+    /// How draws currently count (CountSeparately unless the admin changed it)
+    pub fn get_draw_scoring(env: Env) -> DrawScoring {
+        env.storage()
+            .persistent()
+            .get(&DataKey::DrawScoring)
+            .unwrap_or(DrawScoring::CountSeparately)
+    }
+    
+    /// Current cap on concurrent active games
+    pub fn get_max_active_games(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MaxActiveGames)
+            .unwrap_or(DEFAULT_MAX_ACTIVE_GAMES)
+    }
+    
+    /// Cap the number of bet entries a game can hold (admin only)
+    /// place_bet fails with Error::BetsFull once the cap is reached
+    pub fn set_max_bets_per_game(env: Env, max_bets: u32) {
+        Self::require_admin(&env);
+        env.storage().persistent().set(&DataKey::MaxBetsPerGame, &max_bets);
+    }
+    
+    /// Current cap on bet entries per game
+    pub fn get_max_bets_per_game(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MaxBetsPerGame)
+            .unwrap_or(DEFAULT_MAX_BETS_PER_GAME)
+    }
+    
+    /// Set the bonus paid for revealing immediately once both moves are
+    /// committed (admin only, 0 = off). It decays linearly to zero at the
+    /// reveal deadline and is paid when a decided game settles, out of the
+    /// house fee that game generated: each revealer gets at most half of it,
+    /// so draws and fee-free games earn nothing.
+    pub fn set_reveal_bonus(env: Env, max_bonus: i128) {
+        Self::require_admin(&env);
+        if max_bonus < 0 {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        env.storage().persistent().set(&DataKey::RevealBonus, &max_bonus);
+    }
+    
+    /// Early-reveal bonus paid at the very start of a reveal window
+    pub fn get_reveal_bonus(env: Env) -> i128 {
+        env.storage().persistent().get(&DataKey::RevealBonus).unwrap_or(0)
+    }
+    
+    /// Set how many ledgers winnings credited to internal balances stay
+    /// locked before they can be withdrawn (admin only, 0 = no lock)
+    /// Deposits are never locked; only settle-internal game payouts are.
+    /// At most MAX_WITHDRAWAL_TIMELOCK ledgers.
+    pub fn set_withdrawal_timelock(env: Env, ledgers: u32) {
+        Self::require_admin(&env);
+        if ledgers > MAX_WITHDRAWAL_TIMELOCK {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        env.storage().persistent().set(&DataKey::WithdrawalTimelock, &ledgers);
+    }
+    
+    /// Ledgers a winnings credit stays locked (0 when unset)
+    pub fn get_withdrawal_timelock(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::WithdrawalTimelock).unwrap_or(0)
+    }
+    
+    /// Remember each player's last `limit` revealed salts and reject reveals
+    /// that reuse one of them, in any game (admin only, 0 = off)
+    pub fn set_salt_history(env: Env, limit: u32) {
+        Self::require_admin(&env);
+        if limit > MAX_SALT_HISTORY {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        env.storage().persistent().set(&DataKey::SaltHistoryLimit, &limit);
+    }
+    
+    /// Salts remembered per player (0 when the check is off)
+    pub fn get_salt_history(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::SaltHistoryLimit).unwrap_or(0)
+    }
+    
+    /// Set the ID the next created game will receive (admin only)
+    /// Useful for deterministic deployments, migrations and integration tests.
+    /// The counter can only move forward so existing game IDs are never reused.
+    pub fn set_game_counter(env: Env, value: u64) {
+        Self::require_admin(&env);
+        
+        let current: u64 = env.storage()
+            .persistent()
+            .get(&DataKey::GameCounter)
+            .unwrap_or(1);
+        if value < current {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        
+        env.storage().persistent().set(&DataKey::GameCounter, &value);
+    }
+    
+    /// Configure how many moves new games accept (admin only)
+    /// Supported sets are 3 (classic) and 5 (adds Lizard and Spock).
+    /// Existing games keep the move set they were created with.
+    pub fn set_move_count(env: Env, move_count: u32) {
+        Self::require_admin(&env);
+        if move_count != DEFAULT_MOVE_COUNT && move_count != EXTENDED_MOVE_COUNT {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        
+        env.storage().persistent().set(&DataKey::MoveCount, &move_count);
+    }
+    
+    /// Number of valid moves for new games (moves are numbered 1..=count)
+    pub fn get_move_count(env: Env) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MoveCount)
+            .unwrap_or(DEFAULT_MOVE_COUNT)
+    }
+    
+    /// Fees collected so far in a given token
+    pub fn get_treasury(env: Env, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Treasury(token))
+            .unwrap_or(0)
+    }
+    
+    /// Withdraw collected fees in a given token (admin only)
+    pub fn withdraw_treasury(env: Env, token: Address, to: Address, amount: i128) {
+        Self::require_admin(&env);
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        
+        let key = DataKey::Treasury(token.clone());
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if amount > balance {
+            panic_with_error!(&env, Error::InsufficientFunds);
+        }
+        
+        env.storage().persistent().set(&key, &(balance - amount));
+        
+        let token_client = token::Client::new(&env, &token);
+        token_client.transfer(&env.current_contract_address(), &to, &amount);
+    }
+    
+    /// Bootstrap an event: register every entry's address and credit their
+    /// internal balance with the paired amount (admin only)
+    /// The admin's wallet funds the whole batch in one transfer, and the
+    /// contract checks it received exactly what it credits.
+    pub fn seed_players(env: Env, entries: Vec<(Address, i128)>, token_address: Address) -> i128 {
+        let admin = Self::require_admin(&env);
+        
+        let mut total: i128 = 0;
+        for (_, amount) in entries.iter() {
+            if amount <= 0 {
+                panic_with_error!(&env, Error::InvalidAmount);
+            }
+            total = total
+                .checked_add(amount)
+                .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidAmount));
+        }
+        
+        let token_client = token::Client::new(&env, &token_address);
+        let contract = env.current_contract_address();
+        let before = token_client.balance(&contract);
+        token_client.transfer(&admin, &contract, &total);
+        if token_client.balance(&contract) - before != total {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        
+        for (player, amount) in entries.iter() {
+            Self::ensure_registered(&env, &player);
+            Self::credit_balance(&env, &player, &token_address, amount);
+        }
+        
+        total
+    }
+    
+    // ========================================================================
+    // PLAYER MANAGEMENT
+    // ========================================================================
+    
+    /// Register a new player or retrieve existing player data
+    /// This function is idempotent - calling it multiple times is safe
+    pub fn register_player(env: Env, player: Address) -> Player {
+        // Verify that the caller is authorized to register this address
+        // This prevents someone from registering another person's address
+        player.require_auth();
+        
+        Self::ensure_registered(&env, &player)
+    }
+    
+    /// Estimated value of playing `opponent_rating` at `stake`:
+    /// win probability (from the Elo formula) * pot - stake
+    /// Fees and draws are ignored, so this is a guide for the UI rather than
+    /// an exact payout. Positive means the player is favored.
+    pub fn expected_value(env: Env, player: Address, stake: i128, opponent_rating: u32) -> i128 {
+        let profile: Player = env.storage()
+            .persistent()
+            .get(&DataKey::Player(player))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::PlayerNotRegistered));
+        
+        let win_bps = Self::win_probability_bps(profile.rating, opponent_rating);
+        let weighted_pot = stake
+            .checked_mul(2)
+            .and_then(|pot| pot.checked_mul(win_bps))
+            .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidStake));
+        weighted_pot / BPS_DENOMINATOR - stake
+    }
+    
+    /// Rounds won and lost with each move the player has used, as
+    /// (move, wins, losses) ordered by move; drawn rounds are not counted
+    pub fn get_move_performance(env: Env, player: Address) -> Vec<(u32, u32, u32)> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::MovePerformance(player))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// A player's Elo rating (DEFAULT_RATING until they finish a game)
+    pub fn get_leaderboard_rating(env: Env, player: Address) -> u32 {
+        Self::get_player(env.clone(), player)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::PlayerNotRegistered))
+            .rating
+    }
+    
+    /// IDs of the games `player` created or joined, oldest first
+    /// Only the most recent PLAYER_GAMES_LIMIT games are kept.
+    pub fn get_player_games(env: Env, player: Address) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PlayerGames(player))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// Unfinished games from the player's history where it's their move:
+    /// seated but not yet committed this round, or committed but not revealed
+    /// (after player one, when the game enforces reveal order)
+    /// Only the player's own progress is read, so hide_progress games reveal
+    /// nothing about the opponent. Covers the indexed PLAYER_GAMES_LIMIT games.
+    pub fn get_pending_for_player(env: Env, player: Address) -> Vec<u64> {
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        let mut pending = Vec::new(&env);
+        
+        for game_id in Self::get_player_games(env.clone(), player.clone()).iter() {
+            let game: Game = match env.storage().persistent().get(&DataKey::Game(game_id)) {
+                Some(game) => game,
+                None => continue,
+            };
+            if game.player_two.is_none() {
+                continue;
+            }
+            let is_p1 = player == game.player_one;
+            let (commitment, revealed) = if is_p1 {
+                (&game.p1_commitment, game.p1_move > 0)
+            } else {
+                (&game.p2_commitment, game.p2_move > 0)
+            };
+            
+            let my_turn = match game.state {
+                GameState::WaitingForPlayer => *commitment == zero_commitment,
+                GameState::MovesCommitted => {
+                    !revealed && (is_p1 || !game.reveal_order_enforced || game.p1_move > 0)
+                }
+                GameState::Completed => false,
+            };
+            if my_turn {
+                pending.push_back(game_id);
+            }
+        }
+        
+        pending
+    }
+    
+    /// Head-to-head record between `a` and `b` as (a's wins, b's wins, draws)
+    pub fn get_head_to_head(env: Env, a: Address, b: Address) -> (u32, u32, u32) {
+        let (low, high) = if a <= b { (a.clone(), b) } else { (b, a.clone()) };
+        let (low_wins, high_wins, draws): (u32, u32, u32) = env.storage()
+            .persistent()
+            .get(&DataKey::H2H(low.clone(), high))
+            .unwrap_or((0, 0, 0));
+        
+        if a == low {
+            (low_wins, high_wins, draws)
+        } else {
+            (high_wins, low_wins, draws)
+        }
+    }
+    
+    /// The opponent `player` has faced most often in their recent games
+    /// Only the indexed history is scanned (at most PLAYER_GAMES_LIMIT games);
+    /// ties go to whoever they met first. None if they never had an opponent.
+    pub fn get_frequent_opponent(env: Env, player: Address) -> Option<Address> {
+        let games = Self::get_player_games(env.clone(), player.clone());
+        
+        let mut tally: Vec<(Address, u32)> = Vec::new(&env);
+        for game_id in games.iter() {
+            let game: Game = match env.storage().persistent().get(&DataKey::Game(game_id)) {
+                Some(game) => game,
+                None => continue,
+            };
+            let opponent = if game.player_one == player {
+                match game.player_two {
+                    Some(p2) => p2,
+                    None => continue,
+                }
+            } else {
+                game.player_one
+            };
+            
+            match tally.iter().position(|(addr, _)| addr == opponent) {
+                Some(i) => {
+                    let (addr, count) = tally.get_unchecked(i as u32);
+                    tally.set(i as u32, (addr, count + 1));
+                }
+                None => tally.push_back((opponent, 1)),
+            }
+        }
+        
+        let mut best: Option<(Address, u32)> = None;
+        for (addr, count) in tally.iter() {
+            if best.as_ref().is_none_or(|(_, top)| count > *top) {
+                best = Some((addr, count));
+            }
+        }
+        best.map(|(addr, _)| addr)
+    }
+    
+    /// Top players this season, best first (at most LEADERBOARD_SIZE entries)
+    /// Each entry carries its score: season wins, or half-points (2 per win,
+    /// 1 per draw) under DrawScoring::HalfWin.
+    pub fn get_leaderboard(env: Env) -> Vec<(Address, u32)> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Leaderboard)
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// A player's 1-based position on this season's leaderboard
+    /// None for anyone outside the top LEADERBOARD_SIZE (or unregistered).
+    pub fn get_player_rank(env: Env, player: Address) -> Option<u32> {
+        Self::get_leaderboard(env)
+            .iter()
+            .position(|(addr, _)| addr == player)
+            .map(|index| index as u32 + 1)
+    }
+    
+    /// End the current season and start the next one (admin only)
+    /// The live leaderboard is archived under the finished season and cleared;
+    /// season wins restart from zero while lifetime stats are kept. Players are
+    /// reset lazily on their next win, so this never iterates over everyone.
+    pub fn start_season(env: Env) -> u32 {
+        Self::require_admin(&env);
+        
+        let finished = Self::get_current_season(env.clone());
+        let board = Self::get_leaderboard(env.clone());
+        env.storage().persistent().set(&DataKey::SeasonLeaderboard(finished), &board);
+        env.storage().persistent().remove(&DataKey::Leaderboard);
+        
+        let season = finished + 1;
+        env.storage().persistent().set(&DataKey::CurrentSeason, &season);
+        season
+    }
+    
+    /// Number of the running season (0 until the admin starts one)
+    pub fn get_current_season(env: Env) -> u32 {
+        env.storage().persistent().get(&DataKey::CurrentSeason).unwrap_or(0)
+    }
+    
+    /// Final leaderboard of a finished season (empty if it hasn't ended)
+    pub fn get_season_leaderboard(env: Env, season: u32) -> Vec<(Address, u32)> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::SeasonLeaderboard(season))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// A player's wins, losses and draws in one season (zeros if they didn't play)
+    pub fn get_player_season(env: Env, player: Address, season: u32) -> SeasonStats {
+        env.storage()
+            .persistent()
+            .get(&DataKey::PlayerSeason(player, season))
+            .unwrap_or_default()
+    }
+    
+    /// Retrieve player statistics
+    pub fn get_player(env: Env, player: Address) -> Option<Player> {
+        let key = DataKey::Player(player);
+        env.storage().persistent().get(&key)
+    }
+    
+    /// Store which notifications the player wants (bitmask of NOTIFY_* flags)
+    pub fn set_notification_prefs(env: Env, player: Address, prefs: u32) -> Player {
+        player.require_auth();
+        if prefs & !NOTIFY_ALL != 0 {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        
+        let key = DataKey::Player(player);
+        let mut profile: Player = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::PlayerNotRegistered));
+        
+        profile.notification_prefs = prefs;
+        env.storage().persistent().set(&key, &profile);
+        
+        profile
+    }
+    
+    /// Set the handle shown for the player on leaderboards and in UIs
+    /// Names are not unique; an empty name clears it.
+    pub fn set_player_name(env: Env, player: Address, name: String) -> Player {
+        player.require_auth();
+        if name.len() > MAX_NAME_LEN {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        
+        let key = DataKey::Player(player);
+        let mut profile: Player = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::PlayerNotRegistered));
+        
+        profile.name = name;
+        env.storage().persistent().set(&key, &profile);
+        
+        profile
+    }
+    
+    // ========================================================================
+    // GAME MANAGEMENT
+    // ========================================================================
+    
+    /// Create a new game from `config` (see GameConfig for each option)
+    /// The creator becomes player_one and must deposit the stake up front,
+    /// unless the game uses round stakes
+    pub fn create_game(env: Env, creator: Address, config: GameConfig) -> u64 {
+        // Verify the creator authorized this action
+        creator.require_auth();
+        Self::ensure_not_paused(&env);
+        
+        if let Err(error) = Self::validate_game_config(env.clone(), config.clone()) {
+            panic_with_error!(&env, error);
+        }
+        
+        // First-time creators get a default profile on the spot
+        Self::ensure_registered(&env, &creator);
+        
+        // Get next game ID (auto-increment counter)
+        let game_id = Self::get_and_increment_counter(&env);
+        
+        // Transfer stake from creator to contract
+        // This locks the funds until the game completes
+        if config.use_balance {
+            Self::debit_balance(&env, &creator, &config.token, config.stake_amount);
+        } else if !config.round_stakes {
+            let token_client = token::Client::new(&env, &config.token);
+            token_client.transfer(
+                &creator,
+                &env.current_contract_address(),
+                &config.stake_amount,
+            );
+        }
+        if !config.round_stakes {
+            Self::add_to_totals(&env, &creator, config.stake_amount, 0);
+        }
+        
+        // Create game data structure
+        let mut game = Self::new_game(
+            &env,
+            game_id,
+            creator,
+            config.stake_amount,
+            config.token,
+            config.rounds,
+        );
+        game.hide_progress = config.hide_progress;
+        game.move_count = match config.ruleset {
+            Ruleset::House => Self::get_move_count(env.clone()),
+            Ruleset::Classic => DEFAULT_MOVE_COUNT,
+            Ruleset::Extended => EXTENDED_MOVE_COUNT,
+        };
+        game.commit_window = config.commit_window;
+        game.reveal_window = config.reveal_window;
+        game.draw_policy = config.draw_policy;
+        game.reveal_order_enforced = config.reveal_order_enforced;
+        game.round_stakes = config.round_stakes;
+        game.invited = config.invited;
+        game.settle_internal = config.use_balance;
+        game.p2_stake = config.p2_stake.unwrap_or(config.stake_amount);
+        game.reveal_penalty_bps = config.reveal_penalty_bps;
+        
+        // Store game in persistent storage
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        
+        // Add to active games list
+        Self::add_to_active_games(&env, game_id);
+        Self::add_to_player_games(&env, &game.player_one, game_id);
+        Self::extend_game_ttl(&env, &game);
+        env.events().publish(
+            (EVENT_TOPIC, symbol_short!("created")),
+            (game_id, game.player_one, game.stake_amount),
+        );
+        
+        game_id
+    }
+    
+    /// Run every check create_game makes on a configuration, so clients can
+    /// vet one before submitting
+    /// Creator-specific checks (registration, funds) are not covered.
+    pub fn validate_game_config(env: Env, config: GameConfig) -> Result<(), Error> {
+        if config.stake_amount <= 0 {
+            return Err(Error::InvalidStake);
+        }
+        // Round-stake games collect the stake again every round
+        let deposits = if config.round_stakes { 2 * config.rounds as i128 } else { 2 };
+        let pot = match config.p2_stake {
+            Some(p2_stake) if p2_stake <= 0 => return Err(Error::InvalidStake),
+            Some(p2_stake) => config.stake_amount.checked_add(p2_stake),
+            None => config.stake_amount.checked_mul(deposits),
+        };
+        if pot.is_none_or(|pot| pot > MAX_POT) {
+            return Err(Error::InvalidStake);
+        }
+        if !Self::is_token_allowed(env.clone(), config.token.clone()) {
+            return Err(Error::TokenNotAllowed);
+        }
+        
+        // Round stakes are pulled from wallets round by round
+        if config.use_balance && config.round_stakes {
+            return Err(Error::IncompatibleOptions);
+        }
+        // Handicapped stakes are paid once, and a draw refunds each one rather
+        // than halving the pot
+        if config.p2_stake.is_some() && (config.round_stakes || config.draw_policy == DrawPolicy::SplitPot) {
+            return Err(Error::IncompatibleOptions);
+        }
+        if config.reveal_penalty_bps > BPS_DENOMINATOR as u32 {
+            return Err(Error::InvalidPenalty);
+        }
+        // Without a reveal deadline there is no timeout to soften
+        if config.reveal_penalty_bps > 0 && config.reveal_window == 0 {
+            return Err(Error::IncompatibleOptions);
+        }
+        if config.commit_window > MAX_COMMIT_WINDOW || config.reveal_window > MAX_REVEAL_WINDOW {
+            return Err(Error::InvalidArgument);
+        }
+        let (min_stake, max_stake) = Self::get_stake_bounds(env.clone());
+        let p2_stake = config.p2_stake.unwrap_or(config.stake_amount);
+        for stake in [config.stake_amount, p2_stake] {
+            if stake < min_stake || stake > max_stake {
+                return Err(Error::StakeOutOfBounds);
+            }
+        }
+        
+        // Best-of-N needs an odd round count so a majority always exists
+        if !(1..=MAX_ROUNDS).contains(&config.rounds) || config.rounds.is_multiple_of(2) {
+            return Err(Error::InvalidRoundCount);
+        }
+        
+        // Keep the active list (and every scan over it) bounded
+        if Self::get_active_games_count(env.clone()) >= Self::get_max_active_games(env) {
+            return Err(Error::LobbyFull);
+        }
+        
+        Ok(())
+    }
+    
+    /// Open a game on behalf of a new player, co-authorized by a
+    /// sponsor running an onboarding campaign
+    /// The creator still authorizes and pays their own stake and keeps any
+    /// winnings; the sponsor is only recorded on the game. Unregistered
+    /// creators are registered, and each player can be sponsored once, for
+    /// their first game.
+    pub fn create_sponsored_game(
+        env: Env,
+        sponsor: Address,
+        creator: Address,
+        config: GameConfig,
+    ) -> u64 {
+        sponsor.require_auth();
+        if sponsor == creator {
+            panic_with_error!(&env, Error::SelfPlay);
+        }
+        
+        Self::ensure_registered(&env, &creator);
+        if env.storage().persistent().has(&DataKey::PlayerGames(creator.clone())) {
+            panic_with_error!(&env, Error::AlreadyPlayed);
+        }
+        
+        let game_id = Self::create_game(env.clone(), creator, config);
+        
+        let mut game = Self::load_game(&env, game_id);
+        game.sponsor = Some(sponsor);
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        
+        game_id
+    }
+    
+    /// Join an existing game as player_two
+    /// Deposits the game's p2_stake (player one's stake unless handicapped)
+    pub fn join_game(
+        env: Env,
+        game_id: u64,
+        player: Address,
+        token_address: Address,
+    ) -> Game {
+        player.require_auth();
+        Self::ensure_not_paused(&env);
+        
+        // Retrieve the game
+        let mut game = Self::load_game(&env, game_id);
+        
+        // Every check runs before any stake moves, so a rejected join costs
+        // the player nothing. Completed (or reveal-phase) games are not open.
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if game.player_two.is_some() {
+            panic_with_error!(&env, Error::AlreadyJoined);
+        }
+        if player == game.player_one {
+            panic_with_error!(&env, Error::SelfPlay);
+        }
+        if game.invited.as_ref().is_some_and(|invited| *invited != player) {
+            panic_with_error!(&env, Error::InviteOnly);
+        }
+        
+        // First-time joiners get a default profile on the spot
+        Self::ensure_registered(&env, &player);
+        
+        // Transfer stake from joining player to contract
+        if game.settle_internal {
+            Self::debit_balance(&env, &player, &game.token, game.p2_stake);
+        } else if !game.round_stakes {
+            let token_client = token::Client::new(&env, &token_address);
+            token_client.transfer(
+                &player,
+                &env.current_contract_address(),
+                &game.p2_stake,
+            );
+        } else {
+            // Joining is a promise to fund at least the first round, and the
+            // creator must still be able to match it
+            if !Self::can_pull_stake(&env, &game, &player)
+                || !Self::can_pull_stake(&env, &game, &game.player_one)
+            {
+                panic_with_error!(&env, Error::InsufficientFunds);
+            }
+        }
+        if !game.round_stakes {
+            Self::add_to_totals(&env, &player, game.p2_stake, 0);
+        }
+        
+        // Update game with second player
+        Self::add_to_player_games(&env, &player, game_id);
+        game.player_two = Some(player);
+        game.timings.joined = env.ledger().sequence();
+        Self::start_commit_clock(&env, &mut game);
+        if game.round_stakes {
+            Self::collect_round_stakes(&env, &mut game);
+        }
+        
+        // Save updated game
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        Self::extend_game_ttl(&env, &game);
+        env.events().publish(
+            (EVENT_TOPIC, symbol_short!("joined")),
+            (game_id, game.player_two.clone().unwrap()),
+        );
+        
+        game
+    }
+    
+    /// This contract's own address, which holds all escrowed stakes
+    pub fn get_contract_address(env: Env) -> Address {
+        env.current_contract_address()
+    }
+    
+    /// Whether `player` holds at least `amount` of the token in their wallet
+    /// Lets clients disable join/create instead of sending a doomed transaction
+    pub fn can_afford(env: Env, player: Address, amount: i128, token_address: Address) -> bool {
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.balance(&player) >= amount
+    }
+    
+    /// Join the best open game at this stake and token, if there is one
+    /// Among eligible games (open, same stake and token, not your own, not
+    /// reserved for someone else) the one whose creator's rating is closest to
+    /// the caller's wins; ties go to the oldest game. Unregistered callers count
+    /// as DEFAULT_RATING and are registered on joining. Only the first
+    /// QUICK_MATCH_SCAN active games are examined.
+    /// Returns None when nothing matches so the client can create a game instead.
+    pub fn quick_match(env: Env, player: Address, stake_amount: i128, token_address: Address) -> Option<u64> {
+        let rating = Self::get_player(env.clone(), player.clone())
+            .map_or(DEFAULT_RATING, |profile| profile.rating);
+        
+        let mut best: Option<(u64, u32)> = None;
+        for game_id in Self::get_active_games(env.clone()).iter().take(QUICK_MATCH_SCAN as usize) {
+            let game: Game = match env.storage().persistent().get(&DataKey::Game(game_id)) {
+                Some(game) => game,
+                None => continue,
+            };
+            if game.player_two.is_some()
+                || game.state != GameState::WaitingForPlayer
+                || game.p2_stake != stake_amount
+                || game.token != token_address
+                || game.player_one == player
+                || game.invited.as_ref().is_some_and(|invited| *invited != player)
+            {
+                continue;
+            }
+            
+            let creator_rating = Self::get_player(env.clone(), game.player_one)
+                .map_or(DEFAULT_RATING, |creator| creator.rating);
+            let distance = rating.abs_diff(creator_rating);
+            if best.is_none_or(|(_, closest)| distance < closest) {
+                best = Some((game_id, distance));
+            }
+        }
+        
+        let (game_id, _) = best?;
+        Self::join_game(env, game_id, player, token_address);
+        Some(game_id)
+    }
+    
+    /// Merge two lonely open games into one match, with both creators' auth
+    /// game_a's creator keeps their game and game_b's creator takes the empty
+    /// seat, bringing the stake already escrowed in game_b along, so no
+    /// tokens move. game_b is closed with GameOutcome::Merged. game_b's stake
+    /// must be what game_a's empty seat pays, the games must share token and
+    /// funding mode, and neither may use round stakes.
+    pub fn match_open_games(env: Env, game_a: u64, game_b: u64) -> Game {
+        let mut game = Self::load_game(&env, game_a);
+        let mut other = Self::load_game(&env, game_b);
+        game.player_one.require_auth();
+        other.player_one.require_auth();
+        Self::ensure_not_paused(&env);
+        
+        for open in [&game, &other] {
+            if open.state != GameState::WaitingForPlayer {
+                panic_with_error!(&env, Error::InvalidGameState);
+            }
+            if open.player_two.is_some() {
+                panic_with_error!(&env, Error::AlreadyJoined);
+            }
+            if open.round_stakes {
+                panic_with_error!(&env, Error::IncompatibleOptions);
+            }
+        }
+        if game.player_one == other.player_one {
+            panic_with_error!(&env, Error::SelfPlay);
+        }
+        if game.invited.as_ref().is_some_and(|invited| *invited != other.player_one)
+            || other.invited.as_ref().is_some_and(|invited| *invited != game.player_one)
+        {
+            panic_with_error!(&env, Error::InviteOnly);
+        }
+        if game.p2_stake != other.stake_amount
+            || game.token != other.token
+            || game.settle_internal != other.settle_internal
+        {
+            panic_with_error!(&env, Error::IncompatibleOptions);
+        }
+        
+        // Close the second game; its escrowed stake now backs the seat in the first
+        other.state = GameState::Completed;
+        other.outcome = GameOutcome::Merged;
+        other.timings.completed = env.ledger().sequence();
+        Self::remove_from_active_games(&env, game_b);
+        env.storage().persistent().set(&DataKey::Game(game_b), &other);
+        env.events().publish((EVENT_TOPIC, symbol_short!("merged")), (game_b, game_a));
+        
+        Self::add_to_player_games(&env, &other.player_one, game_a);
+        game.player_two = Some(other.player_one.clone());
+        game.timings.joined = env.ledger().sequence();
+        Self::start_commit_clock(&env, &mut game);
+        env.storage().persistent().set(&DataKey::Game(game_a), &game);
+        Self::extend_game_ttl(&env, &game);
+        env.events().publish((EVENT_TOPIC, symbol_short!("joined")), (game_a, other.player_one));
+        
+        game
+    }
+    
+    /// Withdraw an open game nobody has joined and reclaim the stake
+    /// The game is kept as Completed with GameOutcome::Cancelled so its ID
+    /// still resolves in histories.
+    pub fn cancel_game(env: Env, game_id: u64, token_address: Address) -> Game {
+        let mut game = Self::load_game(&env, game_id);
+        game.player_one.require_auth();
+        
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if game.player_two.is_some() {
+            panic_with_error!(&env, Error::AlreadyJoined);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        Self::close_open_game(&env, &mut game, GameOutcome::Cancelled);
+        game
+    }
+    
+    /// Close an open game nobody joined within OPEN_GAME_EXPIRY ledgers of
+    /// its creation, refunding the creator
+    /// Anyone may call this to keep the active list clean.
+    pub fn expire_open_game(env: Env, game_id: u64, token_address: Address) -> Game {
+        let mut game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if game.player_two.is_some() {
+            panic_with_error!(&env, Error::AlreadyJoined);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if env.ledger().sequence() - game.timings.created <= OPEN_GAME_EXPIRY {
+            panic_with_error!(&env, Error::DeadlineNotReached);
+        }
+        
+        Self::close_open_game(&env, &mut game, GameOutcome::Expired);
+        game
+    }
+    
+    /// Challenge the opponent of a finished game to another with the same
+    /// stake and settings
+    /// `player` creates the new game and pays their stake now; the seat
+    /// opposite is reserved for the old opponent, who accepts with join_game.
+    /// Each game can be rematched once; its next_game_id points at the new one.
+    pub fn rematch(env: Env, game_id: u64, player: Address, token_address: Address) -> u64 {
+        let mut game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::Completed {
+            panic_with_error!(&env, Error::GameNotCompleted);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if game.next_game_id.is_some() {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        let p2 = game.player_two.clone().unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent));
+        // Each player keeps their own stake, whoever re-creates the game
+        let (opponent, own_stake, opponent_stake) = if player == game.player_one {
+            (p2, game.stake_amount, game.p2_stake)
+        } else if player == p2 {
+            (game.player_one.clone(), game.p2_stake, game.stake_amount)
+        } else {
+            panic_with_error!(&env, Error::NotAuthorized);
+        };
+        
+        let config = GameConfig {
+            invited: Some(opponent),
+            ..Self::rematch_config(&game, own_stake, opponent_stake)
+        };
+        let rematch_id = Self::create_game(env.clone(), player, config);
+        
+        game.next_game_id = Some(rematch_id);
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        
+        rematch_id
+    }
+    
+    /// Retrieve game data
+    /// Games created with hide_progress report zeroed commitments (and commit
+    /// timings) until both players have committed, so nobody can tell who is
+    /// still deciding
+    pub fn get_game(env: Env, game_id: u64) -> Option<Game> {
+        let mut game: Game = env.storage().persistent().get(&DataKey::Game(game_id))?;
+        
+        if Self::progress_hidden(&env, &game) {
+            let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+            game.p1_commitment = zero_commitment.clone();
+            game.p2_commitment = zero_commitment;
+            Self::mask_commit_timings(&mut game.timings);
+        }
+        
+        Some(game)
+    }
+    
+    /// Current round and score of a match, e.g. for "Round 2 of 5, 1-0"
+    pub fn get_match_status(env: Env, game_id: u64) -> MatchStatus {
+        let game = Self::load_game(&env, game_id);
+        
+        MatchStatus {
+            current_round: game.current_round,
+            rounds: game.rounds,
+            p1_round_wins: game.p1_round_wins,
+            p2_round_wins: game.p2_round_wins,
+            wins_needed: Self::wins_needed(&game),
+        }
+    }
+    
+    /// Ledgers at which the game was created, joined, sealed and completed
+    /// Per-player commit ledgers read 0 while hide_progress keeps them secret.
+    pub fn get_phase_timings(env: Env, game_id: u64) -> PhaseTimings {
+        let mut game = Self::load_game(&env, game_id);
+        if Self::progress_hidden(&env, &game) {
+            Self::mask_commit_timings(&mut game.timings);
+        }
+        game.timings
+    }
+    
+    /// Commit/reveal progress of the current round from `viewer`'s perspective
+    /// For hide_progress games the viewer must authorize the call, and only
+    /// sees their own commit status until both players have committed
+    pub fn get_game_progress(env: Env, game_id: u64, viewer: Address) -> GameProgress {
+        let game = Self::load_game(&env, game_id);
+        
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        let mut progress = GameProgress {
+            round: game.current_round,
+            p1_committed: game.p1_commitment != zero_commitment,
+            p2_committed: game.p2_commitment != zero_commitment,
+            p1_revealed: game.p1_move > 0,
+            p2_revealed: game.p2_move > 0,
+        };
+        
+        if Self::progress_hidden(&env, &game) {
+            viewer.require_auth();
+            if viewer != game.player_one {
+                progress.p1_committed = false;
+            }
+            if Some(viewer) != game.player_two {
+                progress.p2_committed = false;
+            }
+        }
+        
+        progress
+    }
+    
+    /// Pay SCOUT_COST_BPS of the stake to learn, for the current round only,
+    /// whether the opponent has committed yet (read via get_scout_report)
+    /// Only meaningful for hide_progress games, where commit status is secret.
+    /// Must be bought before committing; the fee goes to the treasury.
+    pub fn buy_scout(env: Env, game_id: u64, player: Address, token_address: Address) -> i128 {
+        player.require_auth();
+        
+        let game = Self::load_game(&env, game_id);
+        if !game.hide_progress {
+            panic_with_error!(&env, Error::ScoutUnavailable);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if game.player_two.is_none() {
+            panic_with_error!(&env, Error::NoOpponent);
+        }
+        
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        let own_commitment = if player == game.player_one {
+            &game.p1_commitment
+        } else if Some(player.clone()) == game.player_two {
+            &game.p2_commitment
+        } else {
+            panic_with_error!(&env, Error::NotAuthorized);
+        };
+        if *own_commitment != zero_commitment {
+            panic_with_error!(&env, Error::AlreadyCommitted);
+        }
+        
+        let key = DataKey::Scouts(game_id);
+        let mut scouts: Vec<(Address, u32)> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(&env));
+        let purchase = (player.clone(), game.current_round);
+        if scouts.contains(&purchase) {
+            panic_with_error!(&env, Error::ScoutUnavailable);
+        }
+        
+        let cost = game.stake_amount * SCOUT_COST_BPS / BPS_DENOMINATOR;
+        if cost > 0 {
+            let token_client = token::Client::new(&env, &token_address);
+            token_client.transfer(&player, &env.current_contract_address(), &cost);
+            Self::credit_treasury(&env, &token_address, cost);
+        }
+        
+        scouts.push_back(purchase);
+        env.storage().persistent().set(&key, &scouts);
+        
+        cost
+    }
+    
+    /// Whether the viewer's opponent has committed in the current round
+    /// Requires a scout bought this round. Only ever reports that one bit,
+    /// never the commitment itself, so nothing about the move can leak.
+    pub fn get_scout_report(env: Env, game_id: u64, viewer: Address) -> bool {
+        viewer.require_auth();
+        
+        let game = Self::load_game(&env, game_id);
+        let scouts: Vec<(Address, u32)> = env.storage()
+            .persistent()
+            .get(&DataKey::Scouts(game_id))
+            .unwrap_or(Vec::new(&env));
+        if !scouts.contains(&(viewer.clone(), game.current_round)) {
+            panic_with_error!(&env, Error::ScoutNotPurchased);
+        }
+        
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        if viewer == game.player_one {
+            game.p2_commitment != zero_commitment
+        } else {
+            game.p1_commitment != zero_commitment
+        }
+    }
+    
+    /// List all active game IDs
+    /// Reads the whole list in one call, so it can exceed read limits in a
+    /// busy lobby; prefer get_active_games_paged there.
+    pub fn get_active_games(env: Env) -> Vec<u64> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::ActiveGames)
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// Number of active (unfinished) games
+    pub fn get_active_games_count(env: Env) -> u32 {
+        Self::get_active_games(env).len()
+    }
+    
+    /// Up to `limit` active game IDs starting at index `start`
+    /// Returns an empty list once `start` is past the end.
+    pub fn get_active_games_paged(env: Env, start: u32, limit: u32) -> Vec<u64> {
+        let games = Self::get_active_games(env.clone());
+        if start >= games.len() || limit == 0 {
+            return Vec::new(&env);
+        }
+        let end = start.saturating_add(limit).min(games.len());
+        games.slice(start..end)
+    }
+    
+    /// Up to `limit` active game IDs in `state`, skipping the first `start`
+    /// matches
+    /// Scans the active list, so Completed never matches. WaitingForPlayer
+    /// covers both open lobbies and seated games awaiting commitments; check
+    /// player_two to tell them apart.
+    pub fn get_games_by_state(env: Env, state: GameState, start: u32, limit: u32) -> Vec<u64> {
+        let mut matches = Vec::new(&env);
+        let mut skipped = 0;
+        for game_id in Self::get_active_games(env.clone()).iter() {
+            if matches.len() >= limit {
+                break;
+            }
+            let game: Game = match env.storage().persistent().get(&DataKey::Game(game_id)) {
+                Some(game) => game,
+                None => continue,
+            };
+            if game.state != state {
+                continue;
+            }
+            if skipped < start {
+                skipped += 1;
+            } else {
+                matches.push_back(game_id);
+            }
+        }
+        
+        matches
+    }
+    
+    // ========================================================================
+    // COMMIT-REVEAL MECHANISM
+    // ========================================================================
+    
+    /// The commitment reveal_move will check `move_choice` and `salt` against
+    /// in this game, for clients to verify their off-chain computation
+    /// Never send a real salt to an untrusted RPC node: simulating this call
+    /// reveals it.
+    pub fn compute_commitment(env: Env, game_id: u64, move_choice: u32, salt: BytesN<32>) -> BytesN<32> {
+        let game = Self::load_game(&env, game_id);
+        if !(1..=game.move_count).contains(&move_choice) {
+            panic_with_error!(&env, Error::InvalidMove);
+        }
+        
+        Self::calculate_commitment(&env, game_id, move_choice, salt)
+    }
+    
+    /// Submit a move commitment (hash of move + secret salt)
+    /// This hides the move from the opponent until reveal phase
+    /// 
+    /// How to generate commitment off-chain:
+    /// 1. Choose your move (1=Attack, 2=Defense, 3=Magic)
+    /// 2. Generate random 32-byte salt (an all-zero salt is rejected at reveal)
+    /// 3. commitment = SHA256(game_id_bytes || move_bytes || salt_bytes)
+    /// 4. Submit this commitment hash
+    ///
+    /// The game_id is bound into the hash, so a commitment computed for one
+    /// game can never verify when revealed in another.
+    ///
+    /// Resubmitting the opponent's exact commitment bytes (to mirror their move
+    /// and force a draw) is rejected. That only blocks the trivial copy: anyone
+    /// who learns the opponent's move some other way can still commit to it.
+    pub fn commit_move(
+        env: Env,
+        game_id: u64,
+        player: Address,
+        commitment: BytesN<32>,
+    ) -> Game {
+        player.require_auth();
+        
+        let mut game = Self::load_game(&env, game_id);
+        
+        // Ensure game has two players and is taking commitments; settled games
+        // keep zeroed commitments, so without the state check they could be
+        // played (and paid out) again
+        if game.player_two.is_none() || game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        
+        // Create zero bytes for comparison
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        // Zero marks "not committed", so it can't be a commitment itself
+        if commitment == zero_commitment {
+            panic_with_error!(&env, Error::EmptyCommitment);
+        }
+        
+        // Determine if this is player 1 or player 2
+        if player == game.player_one {
+            if game.p1_commitment != zero_commitment {
+                panic_with_error!(&env, Error::AlreadyCommitted);
+            }
+            if commitment == game.p2_commitment {
+                panic_with_error!(&env, Error::DuplicateCommitment);
+            }
+            game.p1_commitment = commitment;
+            game.timings.p1_committed = env.ledger().sequence();
+        } else if Some(player.clone()) == game.player_two {
+            if game.p2_commitment != zero_commitment {
+                panic_with_error!(&env, Error::AlreadyCommitted);
+            }
+            if commitment == game.p1_commitment {
+                panic_with_error!(&env, Error::DuplicateCommitment);
+            }
+            game.p2_commitment = commitment;
+            game.timings.p2_committed = env.ledger().sequence();
+        } else {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        // hide_progress games stay quiet until the second commitment lands
+        if !Self::progress_hidden(&env, &game) {
+            env.events().publish((EVENT_TOPIC, symbol_short!("committed")), (game_id, player));
+        }
+        
+        // If both players committed, advance state
+        if game.p1_commitment != zero_commitment && game.p2_commitment != zero_commitment {
+            Self::enter_reveal_phase(&env, &mut game);
+        }
+        
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        Self::extend_game_ttl(&env, &game);
+        
+        game
+    }
+    
+    /// Replace your own commitment (say, after committing with the wrong salt)
+    /// Only allowed while the opponent hasn't committed; once both have, the
+    /// game is in MovesCommitted and commitments are final.
+    pub fn recommit_move(
+        env: Env,
+        game_id: u64,
+        player: Address,
+        commitment: BytesN<32>,
+    ) -> Game {
+        player.require_auth();
+        
+        let mut game = Self::load_game(&env, game_id);
+        
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if commitment == zero_commitment {
+            panic_with_error!(&env, Error::EmptyCommitment);
+        }
+        
+        if player == game.player_one {
+            if game.p1_commitment == zero_commitment {
+                panic_with_error!(&env, Error::NotCommitted);
+            }
+            if game.p2_commitment != zero_commitment {
+                panic_with_error!(&env, Error::AlreadyCommitted);
+            }
+            game.p1_commitment = commitment;
+            game.timings.p1_committed = env.ledger().sequence();
+        } else if Some(player.clone()) == game.player_two {
+            if game.p2_commitment == zero_commitment {
+                panic_with_error!(&env, Error::NotCommitted);
+            }
+            if game.p1_commitment != zero_commitment {
+                panic_with_error!(&env, Error::AlreadyCommitted);
+            }
+            game.p2_commitment = commitment;
+            game.timings.p2_committed = env.ledger().sequence();
+        } else {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        // Same as commit_move: hide_progress games don't announce who is in
+        if !Self::progress_hidden(&env, &game) {
+            env.events().publish((EVENT_TOPIC, symbol_short!("recommit")), (game_id, player));
+        }
+        
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        Self::extend_game_ttl(&env, &game);
+        
+        game
+    }
+    
+    /// Submit both players' sealed commitments in one transaction
+    /// For trusted tournament operators who collect commitments off-chain:
+    /// requires the operator (admin) plus both players' authorization, and
+    /// moves the game straight to MovesCommitted
+    pub fn commit_both(
+        env: Env,
+        game_id: u64,
+        p1_commitment: BytesN<32>,
+        p2_commitment: BytesN<32>,
+    ) -> Game {
+        Self::require_admin(&env);
+        
+        let mut game = Self::load_game(&env, game_id);
+        
+        let player_two = game.player_two.clone()
+            .unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent));
+        game.player_one.require_auth();
+        player_two.require_auth();
+        
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if game.p1_commitment != zero_commitment {
+            panic_with_error!(&env, Error::AlreadyCommitted);
+        }
+        if game.p2_commitment != zero_commitment {
+            panic_with_error!(&env, Error::AlreadyCommitted);
+        }
+        if p1_commitment == zero_commitment || p2_commitment == zero_commitment {
+            panic_with_error!(&env, Error::EmptyCommitment);
+        }
+        
+        game.p1_commitment = p1_commitment;
+        game.p2_commitment = p2_commitment;
+        game.timings.p1_committed = env.ledger().sequence();
+        game.timings.p2_committed = env.ledger().sequence();
+        Self::enter_reveal_phase(&env, &mut game);
+        for player in [game.player_one.clone(), player_two] {
+            env.events().publish((EVENT_TOPIC, symbol_short!("committed")), (game_id, player));
+        }
+        
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        Self::extend_game_ttl(&env, &game);
+        
+        game
+    }
+    
+    /// Reveal your move and verify it matches the commitment
+    /// This is where the magic happens: the contract validates honesty
+    pub fn reveal_move(
+        env: Env,
+        game_id: u64,
+        player: Address,
+        move_choice: u32,
+        salt: BytesN<32>,
+    ) -> Game {
+        player.require_auth();
+        
+        let mut game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::MovesCommitted {
+            // Tell clients whether they're just waiting on the other player
+            let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+            let p1_committed = game.p1_commitment != zero_commitment;
+            let p2_committed = game.p2_commitment != zero_commitment;
+            if game.state == GameState::WaitingForPlayer && p1_committed != p2_committed {
+                panic_with_error!(&env, Error::OpponentNotCommitted);
+            }
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        
+        // Validate move choice
+        if !(1..=game.move_count).contains(&move_choice) {
+            panic_with_error!(&env, Error::InvalidMove);
+        }
+        
+        // With a zero salt anyone could recover the move by hashing the few
+        // possible moves, so such commitments are never accepted at reveal
+        if salt == BytesN::from_array(&env, &[0u8; 32]) {
+            panic_with_error!(&env, Error::WeakSalt);
+        }
+        
+        // Calculate what the commitment should be
+        let calculated_commitment = Self::calculate_commitment(&env, game_id, move_choice, salt.clone());
+        
+        // Verify and store the revealed move
+        if player == game.player_one {
+            if game.p1_commitment == BytesN::from_array(&env, &[0u8; 32]) {
+                panic_with_error!(&env, Error::NotCommitted);
+            }
+            if calculated_commitment != game.p1_commitment {
+                panic_with_error!(&env, Error::CommitmentMismatch);
+            }
+            if game.p1_move != 0 {
+                panic_with_error!(&env, Error::AlreadyRevealed);
+            }
+            game.p1_move = move_choice;
+            game.p1_salt = salt.clone();
+            game.timings.p1_revealed = env.ledger().sequence();
+        } else if Some(player.clone()) == game.player_two {
+            if game.p2_commitment == BytesN::from_array(&env, &[0u8; 32]) {
+                panic_with_error!(&env, Error::NotCommitted);
+            }
+            if calculated_commitment != game.p2_commitment {
+                panic_with_error!(&env, Error::CommitmentMismatch);
+            }
+            if game.p2_move != 0 {
+                panic_with_error!(&env, Error::AlreadyRevealed);
+            }
+            if game.reveal_order_enforced && game.p1_move == 0 {
+                panic_with_error!(&env, Error::RevealOutOfOrder);
+            }
+            game.p2_move = move_choice;
+            game.p2_salt = salt.clone();
+            game.timings.p2_revealed = env.ledger().sequence();
+        } else {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        Self::record_salt(&env, &player, &salt);
+        Self::count_move(&env, &player, move_choice);
+        env.events().publish(
+            (EVENT_TOPIC, symbol_short!("revealed")),
+            (game_id, player.clone(), move_choice),
+        );
+        
+        // Both salts feed the tiebreak, so neither player can steer it alone
+        game.tiebreak_seed = Self::xor_bytes(&env, &game.tiebreak_seed, &salt);
+        
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        Self::extend_game_ttl(&env, &game);
+        
+        game
+    }
+    
+    /// Check whether a move and salt would verify against the player's stored
+    /// commitment, without mutating state or requiring the reveal phase
+    /// Lets clients confirm they kept the right salt before calling reveal_move
+    pub fn check_reveal(
+        env: Env,
+        game_id: u64,
+        player: Address,
+        move_choice: u32,
+        salt: BytesN<32>,
+    ) -> bool {
+        let game = Self::load_game(&env, game_id);
+        
+        let stored = if player == game.player_one {
+            game.p1_commitment
+        } else if Some(player) == game.player_two {
+            game.p2_commitment
+        } else {
+            return false;
+        };
+        
+        if !(1..=game.move_count).contains(&move_choice)
+            || stored == BytesN::from_array(&env, &[0u8; 32])
+        {
+            return false;
+        }
+        
+        Self::calculate_commitment(&env, game_id, move_choice, salt) == stored
+    }
+    
+    // ========================================================================
+    // GAME RESOLUTION
+    // ========================================================================
+    
+    /// Finalize the current round: determine its winner and, once a player
+    /// holds a majority of the best-of-N rounds, distribute prizes
+    pub fn finalize_game(
+        env: Env,
+        game_id: u64,
+        token_address: Address,
+    ) -> Game {
+        let mut game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::MovesCommitted {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        // Both moves must be revealed (non-zero)
+        if game.p1_move == 0 {
+            panic_with_error!(&env, Error::NotRevealed);
+        }
+        if game.p2_move == 0 {
+            panic_with_error!(&env, Error::NotRevealed);
+        }
+        
+        let p1_move = game.p1_move;
+        let p2_move = game.p2_move;
+        
+        // Determine the round winner using game logic
+        let winner_addr = Self::determine_winner(&game, p1_move, p2_move);
+        
+        match &winner_addr {
+            Some(w) if *w == game.player_one => game.p1_round_wins += 1,
+            Some(_) => game.p2_round_wins += 1,
+            None => {} // Drawn rounds don't count towards the majority
+        }
+        
+        Self::record_round(&env, &game, &winner_addr);
+        if let Some(w) = &winner_addr {
+            let p2 = game.player_two.clone().unwrap();
+            Self::record_move_result(&env, &game.player_one, p1_move, *w == game.player_one);
+            Self::record_move_result(&env, &p2, p2_move, *w == p2);
+        }
+        
+        // Without a majority the match continues with a fresh round
+        let wins_needed = Self::wins_needed(&game);
+        let sudden_death = game.rounds == 1
+            && winner_addr.is_none()
+            && game.draw_policy == DrawPolicy::SuddenDeath;
+        if sudden_death
+            || (game.rounds > 1
+                && game.p1_round_wins < wins_needed
+                && game.p2_round_wins < wins_needed)
+        {
+            Self::start_next_round(&env, &mut game);
+            // Sudden-death replays are played for the stakes already in the pot
+            if game.round_stakes && !sudden_death {
+                Self::collect_round_stakes(&env, &mut game);
+            }
+            env.storage().persistent().set(&DataKey::Game(game_id), &game);
+            Self::extend_game_ttl(&env, &game);
+            return game;
+        }
+        
+        let winner_addr = match (winner_addr, game.draw_policy) {
+            (None, DrawPolicy::HashTiebreak) => Some(Self::tiebreak_by_hash(&env, &game)),
+            (None, DrawPolicy::SuddenDeathRandom) => Some(Self::tiebreak_by_ledger(&env, &game)),
+            (winner_addr, _) => winner_addr,
+        };
+        
+        Self::complete_game(&env, &mut game, winner_addr);
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        
+        game
+    }
+    
+    /// Offer double-or-nothing instead of finalizing a single-shot game
+    /// Only the winner of the revealed round may offer. The opponent either
+    /// accepts with accept_continuation or declines by finalizing as usual.
+    pub fn offer_continuation(env: Env, game_id: u64, player: Address) -> Game {
+        player.require_auth();
+        
+        let mut game = Self::load_game(&env, game_id);
+        if game.state != GameState::MovesCommitted {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if game.p1_move == 0 || game.p2_move == 0 {
+            panic_with_error!(&env, Error::NotRevealed);
+        }
+        if game.rounds != 1 || game.round_stakes {
+            panic_with_error!(&env, Error::IncompatibleOptions);
+        }
+        if game.stake_amount <= 0 {
+            panic_with_error!(&env, Error::IncompatibleOptions);
+        }
+        if game.continuation_offered {
+            panic_with_error!(&env, Error::ContinuationOffered);
+        }
+        
+        let winner = Self::determine_winner(&game, game.p1_move, game.p2_move);
+        if winner != Some(player.clone()) {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        // Doubling must leave the pot within MAX_POT
+        if Self::escrowed(&env, &game).checked_mul(2).is_none_or(|pot| pot > MAX_POT) {
+            panic_with_error!(&env, Error::InvalidStake);
+        }
+        
+        game.continuation_offered = true;
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        env.events().publish((EVENT_TOPIC, symbol_short!("offered")), (game_id, player));
+        
+        game
+    }
+    
+    /// Accept a double-or-nothing offer: both players add their own current
+    /// stake again, doubling the pot, and the round is replayed with fresh
+    /// commitments. The revealed round is discarded (kept only in the round
+    /// history). Needs the offering winner's authorization as well, since
+    /// their stake is pulled too.
+    pub fn accept_continuation(env: Env, game_id: u64, player: Address, token_address: Address) -> Game {
+        player.require_auth();
+        
+        let mut game = Self::load_game(&env, game_id);
+        if !game.continuation_offered {
+            panic_with_error!(&env, Error::NoContinuationOffered);
+        }
+        if game.state != GameState::MovesCommitted {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        let offerer = Self::determine_winner(&game, game.p1_move, game.p2_move)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::InvalidGameState));
+        let p2 = game.player_two.clone().unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent));
+        let opponent = if offerer == game.player_one { p2.clone() } else { game.player_one.clone() };
+        if player != opponent {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        offerer.require_auth();
+        
+        for (payer, stake) in [(&game.player_one, game.stake_amount), (&p2, game.p2_stake)] {
+            if game.settle_internal {
+                Self::debit_balance(&env, payer, &game.token, stake);
+            } else {
+                let token_client = token::Client::new(&env, &game.token);
+                token_client.transfer(payer, &env.current_contract_address(), &stake);
+            }
+            Self::add_to_totals(&env, payer, stake, 0);
+        }
+        game.stake_amount *= 2;
+        game.p2_stake *= 2;
+        
+        Self::record_round(&env, &game, &Some(offerer));
+        game.continuation_offered = false;
+        Self::start_next_round(&env, &mut game);
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        Self::extend_game_ttl(&env, &game);
+        env.events().publish((EVENT_TOPIC, symbol_short!("doubled")), (game_id, game.stake_amount));
+        
+        game
+    }
+    
+    /// Finalize every listed game that is ready (both moves revealed and in
+    /// `token_address`), skipping the rest instead of failing the batch
+    /// Returns the IDs that were finalized, in the order given. At most
+    /// MAINTENANCE_SCAN IDs per call.
+    pub fn finalize_many(env: Env, game_ids: Vec<u64>, token_address: Address) -> Vec<u64> {
+        if game_ids.len() > MAINTENANCE_SCAN {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        
+        let mut finalized = Vec::new(&env);
+        for game_id in game_ids.iter() {
+            let game: Game = match env.storage().persistent().get(&DataKey::Game(game_id)) {
+                Some(game) => game,
+                None => continue,
+            };
+            let ready = game.state == GameState::MovesCommitted
+                && game.token == token_address
+                && game.p1_move > 0
+                && game.p2_move > 0;
+            if ready {
+                Self::finalize_game(env.clone(), game_id, token_address.clone());
+                finalized.push_back(game_id);
+            }
+        }
+        
+        finalized
+    }
+    
+    /// How a settled game ended (None until it is finalized, timed out,
+    /// forfeited or arbitrated)
+    pub fn get_game_result(env: Env, game_id: u64) -> Option<GameResult> {
+        env.storage().persistent().get(&DataKey::GameResult(game_id))
+    }
+    
+    /// Export a finished match for replay viewers: players, stakes, the
+    /// winner and every round's moves in the order they were played
+    pub fn export_replay(env: Env, game_id: u64) -> Replay {
+        let game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::Completed {
+            panic_with_error!(&env, Error::GameNotCompleted);
+        }
+        
+        Replay {
+            game_id,
+            player_one: game.player_one,
+            player_two: game.player_two.unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent)),
+            token: game.token,
+            stake_amount: game.stake_amount,
+            rounds: game.rounds,
+            p1_round_wins: game.p1_round_wins,
+            p2_round_wins: game.p2_round_wins,
+            winner: game.winner,
+            history: Self::get_round_history(env.clone(), game_id),
+        }
+    }
+    
+    /// Bundle a finished game's commitments, reveals and recomputed hashes
+    /// so anyone can check offline that the winner was decided honestly
+    pub fn export_fairness_proof(env: Env, game_id: u64) -> FairnessProof {
+        let game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::Completed {
+            panic_with_error!(&env, Error::GameNotCompleted);
+        }
+        
+        let mut rounds = Vec::new(&env);
+        for record in Self::get_round_history(env.clone(), game_id).iter() {
+            rounds.push_back(RoundProof {
+                round: record.round,
+                p1_recomputed: Self::calculate_commitment(&env, game_id, record.p1_move, record.p1_salt.clone()),
+                p2_recomputed: Self::calculate_commitment(&env, game_id, record.p2_move, record.p2_salt.clone()),
+                p1_commitment: record.p1_commitment,
+                p2_commitment: record.p2_commitment,
+                p1_move: record.p1_move,
+                p2_move: record.p2_move,
+                p1_salt: record.p1_salt,
+                p2_salt: record.p2_salt,
+                winner: record.winner,
+            });
+        }
+        
+        FairnessProof {
+            game_id,
+            player_one: game.player_one,
+            player_two: game.player_two.unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent)),
+            move_count: game.move_count,
+            draw_policy: game.draw_policy,
+            outcome: game.outcome,
+            winner: game.winner,
+            rounds,
+        }
+    }
+    
+    /// How a past game was resolved, or None if it isn't completed
+    /// A lighter read than get_game for history views
+    pub fn get_game_outcome(env: Env, game_id: u64) -> Option<GameOutcome> {
+        let game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::Completed {
+            return None;
+        }
+        Some(game.outcome)
+    }
+    
+    /// Resolved rounds of a game so far (empty if none)
+    pub fn get_round_history(env: Env, game_id: u64) -> Vec<RoundRecord> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::RoundHistory(game_id))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// Settle a round whose commit deadline passed without both commitments
+    /// Anyone may call this. If exactly one player committed they take the
+    /// whole pot (no fee) and the win; if neither did, both are refunded.
+    pub fn claim_commit_timeout(env: Env, game_id: u64, token_address: Address) -> Game {
+        let mut game = Self::load_game(&env, game_id);
+        
+        if game.state != GameState::WaitingForPlayer {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if game.player_two.is_none() {
+            panic_with_error!(&env, Error::NoOpponent);
+        }
+        if game.commit_window == 0 {
+            panic_with_error!(&env, Error::TimeoutNotAvailable);
+        }
+        if env.ledger().sequence() <= game.commit_deadline {
+            panic_with_error!(&env, Error::DeadlineNotReached);
+        }
+        
+        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        let winner = match (game.p1_commitment != zero_commitment, game.p2_commitment != zero_commitment) {
+            (true, false) => Some(game.player_one.clone()),
+            (false, true) => game.player_two.clone(),
+            _ => None,
+        };
+        Self::settle_early(&env, &mut game, winner, GameOutcome::TimedOut, 0);
+        
+        game
+    }
+    
+    /// Settle a game whose reveal deadline passed with a reveal missing
+    /// Anyone may call this. A player who revealed a valid move takes the
+    /// whole pot (no fee) and the win over the no-show; if neither revealed,
+    /// both are refunded. Games with a reveal penalty settle a lone reveal
+    /// through claim_reveal_timeout_partial instead.
+    pub fn claim_reveal_timeout(env: Env, game_id: u64, token_address: Address) -> Game {
+        let mut game = Self::load_game(&env, game_id);
+        let winner = Self::reveal_timeout_winner(&env, &game, &token_address);
+        if winner.is_some() && game.reveal_penalty_bps > 0 {
+            panic_with_error!(&env, Error::TimeoutNotAvailable);
+        }
+        Self::settle_early(&env, &mut game, winner, GameOutcome::TimedOut, 0);
+        
+        game
+    }
+    
+    /// Settle an overdue reveal under the game's reveal_penalty_bps
+    /// Anyone may call this. The player who revealed wins and gets their own
+    /// stake plus penalty_bps of the no-show's stake (no fee); the no-show is
+    /// refunded the rest of theirs. If neither revealed, both are refunded.
+    pub fn claim_reveal_timeout_partial(env: Env, game_id: u64, token_address: Address) -> Game {
+        let mut game = Self::load_game(&env, game_id);
+        if game.reveal_penalty_bps == 0 {
+            panic_with_error!(&env, Error::TimeoutNotAvailable);
+        }
+        let winner = Self::reveal_timeout_winner(&env, &game, &token_address);
+        
+        let refund = match &winner {
+            Some(winner) => {
+                let (p1_stake, p2_stake) = if game.round_stakes {
+                    (game.pot / 2, game.pot / 2)
+                } else {
+                    (game.stake_amount, game.p2_stake)
+                };
+                let no_show_stake = if *winner == game.player_one { p2_stake } else { p1_stake };
+                no_show_stake - no_show_stake * game.reveal_penalty_bps as i128 / BPS_DENOMINATOR
+            }
+            None => 0,
+        };
+        Self::settle_early(&env, &mut game, winner, GameOutcome::TimedOut, refund);
+        
+        game
+    }
+    
+    /// Concede a game that has both players seated, handing the opponent the
+    /// whole pot (no fee) and the win
+    pub fn forfeit(env: Env, game_id: u64, player: Address, token_address: Address) -> Game {
+        player.require_auth();
+        
+        let mut game = Self::load_game(&env, game_id);
+        
+        if game.state == GameState::Completed {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        let p2 = game.player_two.clone().unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent));
+        let opponent = if player == game.player_one {
+            p2
+        } else if player == p2 {
+            game.player_one.clone()
+        } else {
+            panic_with_error!(&env, Error::NotAuthorized);
+        };
+        
+        Self::settle_early(&env, &mut game, Some(opponent), GameOutcome::Forfeited, 0);
+        
+        game
+    }
+    
+    // ========================================================================
+    // SPECTATOR BETTING
+    // ========================================================================
+    
+    /// Bet on one of the players of a game that has both moves committed
+    /// Betting closes as soon as either move is revealed, and players can't
+    /// bet on their own game. A repeat bet on the same side adds to the
+    /// bettor's entry; new entries stop at the per-game cap (Error::BetsFull)
+    pub fn place_bet(
+        env: Env,
+        game_id: u64,
+        bettor: Address,
+        on_player: Address,
+        amount: i128,
+        token_address: Address,
+    ) -> (i128, i128) {
+        bettor.require_auth();
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        
+        let game = Self::load_game(&env, game_id);
+        Self::assert_betting_open(&env, &game);
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        let player_two = game.player_two.clone().unwrap();
+        if bettor == game.player_one || bettor == player_two {
+            panic_with_error!(&env, Error::SelfPlay);
+        }
+        if !(on_player == game.player_one || on_player == player_two) {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        
+        // Repeat bets on the same side top up the bettor's existing entry
+        let mut bets = Self::get_bets(env.clone(), game_id);
+        let existing = bets
+            .iter()
+            .position(|bet| bet.bettor == bettor && bet.on_player == on_player);
+        if existing.is_none() && bets.len() >= Self::get_max_bets_per_game(env.clone()) {
+            panic_with_error!(&env, Error::BetsFull);
+        }
+        
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&bettor, &env.current_contract_address(), &amount);
+        
+        let on_player_one = on_player == game.player_one;
+        match existing {
+            Some(index) => {
+                let mut bet = bets.get_unchecked(index as u32);
+                bet.amount += amount;
+                bets.set(index as u32, bet);
+            }
+            None => bets.push_back(Bet { bettor, on_player, amount }),
+        }
+        env.storage().persistent().set(&DataKey::Bets(game_id), &bets);
+        Self::adjust_bet_escrow(&env, &game.token, amount);
+        
+        let (mut p1_total, mut p2_total) = Self::get_bet_totals(env.clone(), game_id);
+        if on_player_one {
+            p1_total += amount;
+        } else {
+            p2_total += amount;
+        }
+        env.storage().persistent().set(&DataKey::BetTotals(game_id), &(p1_total, p2_total));
+        
+        (p1_total, p2_total)
+    }
+    
+    /// Withdraw all of a bettor's bets on a game while betting is still open
+    /// Returns the refunded amount
+    pub fn cancel_bet(env: Env, game_id: u64, bettor: Address) -> i128 {
+        bettor.require_auth();
+        
+        let game = Self::load_game(&env, game_id);
+        Self::assert_betting_open(&env, &game);
+        
+        let (mut p1_total, mut p2_total) = Self::get_bet_totals(env.clone(), game_id);
+        let mut kept = Vec::new(&env);
+        let mut refund: i128 = 0;
+        for bet in Self::get_bets(env.clone(), game_id).iter() {
+            if bet.bettor != bettor {
+                kept.push_back(bet);
+                continue;
+            }
+            refund += bet.amount;
+            if bet.on_player == game.player_one {
+                p1_total -= bet.amount;
+            } else {
+                p2_total -= bet.amount;
+            }
+        }
+        if refund <= 0 {
+            panic_with_error!(&env, Error::NoBets);
+        }
+        
+        env.storage().persistent().set(&DataKey::Bets(game_id), &kept);
+        env.storage().persistent().set(&DataKey::BetTotals(game_id), &(p1_total, p2_total));
+        Self::adjust_bet_escrow(&env, &game.token, -refund);
+        
+        let token_client = token::Client::new(&env, &game.token);
+        token_client.transfer(&env.current_contract_address(), &bettor, &refund);
+        
+        refund
+    }
+    
+    /// Pay out the bets on a completed game
+    /// Backers of the winner split the whole pool pro rata to their stake (any
+    /// rounding dust goes to the first winning bet). Draws, or a winner nobody
+    /// backed, refund every bet.
+    pub fn settle_bets(env: Env, game_id: u64, token_address: Address) -> Vec<Bet> {
+        let game = Self::load_game(&env, game_id);
+        if game.state != GameState::Completed {
+            panic_with_error!(&env, Error::GameNotCompleted);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        let bets = Self::get_bets(env.clone(), game_id);
+        if bets.is_empty() {
+            panic_with_error!(&env, Error::NoBets);
+        }
+        
+        let (p1_total, p2_total) = Self::get_bet_totals(env.clone(), game_id);
+        let pool = p1_total + p2_total;
+        let winning_total = match &game.winner {
+            Some(w) if *w == game.player_one => p1_total,
+            Some(_) => p2_total,
+            None => 0,
+        };
+        
+        // Work out every payout first so the shares sum exactly to the pool
+        let mut payouts = Vec::new(&env);
+        let mut paid: i128 = 0;
+        let mut first_winner: Option<u32> = None;
+        for (index, bet) in bets.iter().enumerate() {
+            let payout = if winning_total == 0 {
+                bet.amount
+            } else if Some(bet.on_player.clone()) == game.winner {
+                first_winner.get_or_insert(index as u32);
+                bet.amount * pool / winning_total
+            } else {
+                0
+            };
+            paid += payout;
+            payouts.push_back(payout);
+        }
+        if let Some(index) = first_winner {
+            payouts.set(index, payouts.get_unchecked(index) + (pool - paid));
+        }
+        
+        let token_client = token::Client::new(&env, &game.token);
+        for (index, bet) in bets.iter().enumerate() {
+            let payout = payouts.get_unchecked(index as u32);
+            if payout > 0 {
+                token_client.transfer(&env.current_contract_address(), &bet.bettor, &payout);
+            }
+        }
+        
+        env.storage().persistent().remove(&DataKey::Bets(game_id));
+        env.storage().persistent().remove(&DataKey::BetTotals(game_id));
+        Self::adjust_bet_escrow(&env, &game.token, -pool);
+        
+        bets
+    }
+    
+    /// Unsettled bets on a game
+    pub fn get_bets(env: Env, game_id: u64) -> Vec<Bet> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Bets(game_id))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// Total wagered on (player_one, player_two), for showing implied odds
+    /// Back to (0, 0) once the bets are settled.
+    pub fn get_bet_totals(env: Env, game_id: u64) -> (i128, i128) {
+        env.storage()
+            .persistent()
+            .get(&DataKey::BetTotals(game_id))
+            .unwrap_or((0, 0))
+    }
+    
+    // ========================================================================
+    // COMMENTS
+    // ========================================================================
+    
+    /// Anchor a comment on a game by its hash (text is stored off-chain)
+    /// Only participants can comment; the list is append-only
+    pub fn post_comment(env: Env, game_id: u64, author: Address, comment_hash: BytesN<32>) -> u32 {
+        author.require_auth();
+        
+        let game = Self::load_game(&env, game_id);
+        if !(author == game.player_one || Some(author.clone()) == game.player_two) {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        
+        let key = DataKey::Comments(game_id);
+        let mut comments: Vec<(Address, BytesN<32>)> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(&env));
+        comments.push_back((author, comment_hash));
+        env.storage().persistent().set(&key, &comments);
+        
+        comments.len()
+    }
+    
+    /// All comment hashes anchored on a game, oldest first
+    pub fn get_comments(env: Env, game_id: u64) -> Vec<(Address, BytesN<32>)> {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Comments(game_id))
+            .unwrap_or(Vec::new(&env))
+    }
+    
+    /// Mark a game's comments as moderated (admin only)
+    /// Hashes stay on-chain; clients are expected to stop displaying them
+    pub fn set_moderated(env: Env, game_id: u64, moderated: bool) -> Game {
+        Self::require_admin(&env);
+        
+        let mut game = Self::load_game(&env, game_id);
+        game.moderated = moderated;
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        
+        game
+    }
+    
+    // ========================================================================
+    // DISPUTES
+    // ========================================================================
+    
+    /// Flag a completed or stuck game for admin arbitration
+    /// Only participants can flag, and each game can be disputed once
+    pub fn flag_dispute(env: Env, game_id: u64, player: Address, reason_code: u32) -> Dispute {
+        player.require_auth();
+        
+        let game = Self::load_game(&env, game_id);
+        if !(player == game.player_one || Some(player.clone()) == game.player_two) {
+            panic_with_error!(&env, Error::NotAuthorized);
+        }
+        
+        let key = DataKey::Dispute(game_id);
+        if env.storage().persistent().has(&key) {
+            panic_with_error!(&env, Error::DisputeExists);
+        }
+        
+        let dispute = Dispute {
+            game_id,
+            flagged_by: player,
+            reason_code,
+            flagged_ledger: env.ledger().sequence(),
+            resolution: DisputeResolution::Unresolved,
+        };
+        env.storage().persistent().set(&key, &dispute);
+        
+        dispute
+    }
+    
+    /// Retrieve the dispute flagged on a game, if any
+    pub fn get_dispute(env: Env, game_id: u64) -> Option<Dispute> {
+        env.storage().persistent().get(&DataKey::Dispute(game_id))
+    }
+    
+    /// Resolve a flagged dispute (admin only)
+    /// Completed games can only be Dismissed or Upheld since their pot is
+    /// already paid out. Unfinished games can also be settled: RefundBoth
+    /// returns every deposited stake, Award* sends the whole pot to one player.
+    pub fn resolve_dispute(
+        env: Env,
+        game_id: u64,
+        resolution: DisputeResolution,
+        token_address: Address,
+    ) -> Dispute {
+        Self::require_admin(&env);
+        
+        let key = DataKey::Dispute(game_id);
+        let mut dispute: Dispute = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(&env, Error::DisputeNotFound));
+        if dispute.resolution != DisputeResolution::Unresolved {
+            panic_with_error!(&env, Error::DisputeResolved);
+        }
+        
+        let mut game = Self::load_game(&env, game_id);
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        match resolution {
+            DisputeResolution::Unresolved => panic_with_error!(&env, Error::InvalidArgument),
+            DisputeResolution::Dismissed | DisputeResolution::Upheld => {}
+            settlement => {
+                if game.state == GameState::Completed {
+                    panic_with_error!(&env, Error::InvalidGameState);
+                }
+                
+                let pot = Self::escrowed(&env, &game);
+                match settlement {
+                    DisputeResolution::RefundBoth => {
+                        if game.player_two.is_some() {
+                            Self::refund_stakes(&env, &game, pot);
+                        } else if pot > 0 {
+                            Self::pay_out(&env, &game, &game.player_one, pot);
+                        }
+                    }
+                    DisputeResolution::AwardPlayerOne => {
+                        Self::pay_out(&env, &game, &game.player_one, pot);
+                        game.winner = Some(game.player_one.clone());
+                    }
+                    _ => {
+                        let p2 = game.player_two.clone()
+                            .unwrap_or_else(|| panic_with_error!(&env, Error::NoOpponent));
+                        Self::pay_out(&env, &game, &p2, pot);
+                        game.winner = Some(p2);
+                    }
+                }
+                
+                game.state = GameState::Completed;
+                game.outcome = GameOutcome::Arbitrated;
+                game.timings.completed = env.ledger().sequence();
+                Self::remove_from_active_games(&env, game_id);
+                env.storage().persistent().set(&DataKey::Game(game_id), &game);
+                Self::record_finalized(&env, &game, pot);
+            }
+        }
+        
+        dispute.resolution = resolution;
+        env.storage().persistent().set(&key, &dispute);
+        
+        dispute
+    }
+    
+    /// Move the pot of a long-abandoned game into the treasury (admin only)
+    /// A last resort for pots orphaned by lost keys on both sides: the game
+    /// must be seated and idle for more than ABANDONED_GAME_EXPIRY ledgers,
+    /// with no timeout claim or finalize able to settle it instead.
+    pub fn admin_sweep_expired(env: Env, game_id: u64, token_address: Address) -> Game {
+        Self::require_admin(&env);
+        
+        let mut game = Self::load_game(&env, game_id);
+        if game.state == GameState::Completed {
+            panic_with_error!(&env, Error::InvalidGameState);
+        }
+        if token_address != game.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if game.player_two.is_none() {
+            panic_with_error!(&env, Error::NoOpponent);
+        }
+        
+        let now = env.ledger().sequence();
+        let timings = &game.timings;
+        let last_activity = timings.created
+            .max(timings.joined)
+            .max(timings.p1_committed)
+            .max(timings.p2_committed)
+            .max(timings.committed)
+            .max(timings.p1_revealed)
+            .max(timings.p2_revealed);
+        if now - last_activity <= ABANDONED_GAME_EXPIRY {
+            panic_with_error!(&env, Error::DeadlineNotReached);
+        }
+        
+        let settleable = match game.state {
+            GameState::WaitingForPlayer => game.commit_window > 0 && now > game.commit_deadline,
+            _ => {
+                (game.p1_move > 0 && game.p2_move > 0)
+                    || (game.reveal_window > 0 && now > game.reveal_deadline)
+            }
+        };
+        if settleable {
+            panic_with_error!(&env, Error::TimeoutNotAvailable);
+        }
+        
+        let pot = Self::escrowed(&env, &game);
+        if pot > 0 {
+            Self::credit_treasury(&env, &game.token, pot);
+        }
+        
+        game.state = GameState::Completed;
+        game.outcome = GameOutcome::Swept;
+        game.timings.completed = now;
+        Self::remove_from_active_games(&env, game_id);
+        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        env.events().publish((EVENT_TOPIC, symbol_short!("swept")), (game_id, pot));
+        
+        game
+    }
+    
+    // ========================================================================
+    // INTERNAL BALANCES
+    // ========================================================================
+    
+    /// Deposit tokens into the player's internal balance held by the contract
+    /// Internal balances fund stakes without a per-game transfer (games
+    /// created with use_balance, and auto-rematches)
+    pub fn deposit(env: Env, player: Address, amount: i128, token_address: Address) -> i128 {
+        player.require_auth();
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&player, &env.current_contract_address(), &amount);
+        
+        Self::credit_balance(&env, &player, &token_address, amount)
+    }
+    
+    /// Withdraw unused internal balance back to the player's wallet
+    /// Winnings still inside the withdrawal timelock can't be withdrawn
+    /// (DeadlineNotReached); amounts past the balance are InsufficientFunds
+    pub fn withdraw(env: Env, player: Address, amount: i128, token_address: Address) -> i128 {
+        player.require_auth();
+        if amount <= 0 {
+            panic_with_error!(&env, Error::InvalidAmount);
+        }
+        if amount > Self::get_balance(env.clone(), player.clone(), token_address.clone()) {
+            panic_with_error!(&env, Error::InsufficientFunds);
+        }
+        if amount > Self::get_withdrawable(env.clone(), player.clone(), token_address.clone()) {
+            panic_with_error!(&env, Error::DeadlineNotReached);
+        }
+        
+        let remaining = Self::debit_balance(&env, &player, &token_address, amount);
+        
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&env.current_contract_address(), &player, &amount);
+        
+        remaining
+    }
+    
+    /// Portion of a player's internal balance that is not timelocked
+    /// Locked credits still count in full even if they were re-staked, so
+    /// this never overstates what may leave the contract
+    pub fn get_withdrawable(env: Env, player: Address, token: Address) -> i128 {
+        let balance = Self::get_balance(env.clone(), player.clone(), token.clone());
+        let locked = Self::prune_locked_credits(&env, &player, &token);
+        (balance - locked).max(0)
+    }
+    
+    /// Retrieve a player's internal balance in a given token
+    pub fn get_balance(env: Env, player: Address, token: Address) -> i128 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Balance(player, token))
+            .unwrap_or(0)
+    }
+    
+    // ========================================================================
+    // AUTO-REMATCH
+    // ========================================================================
+    
+    /// Opt in (or out) of automatically rematching a specific opponent
+    /// Opting in grants AUTO_REMATCH_LIMIT rematches; after that the player
+    /// must opt in again, so a forgotten flag can't drain their balance forever
+    pub fn set_auto_rematch(env: Env, player: Address, opponent: Address, enabled: bool) {
+        player.require_auth();
+        if player == opponent {
+            panic_with_error!(&env, Error::SelfPlay);
+        }
+        
+        let key = DataKey::AutoRematch(player, opponent);
+        if enabled {
+            env.storage().persistent().set(&key, &AUTO_REMATCH_LIMIT);
+        } else {
+            env.storage().persistent().remove(&key);
+        }
+    }
+    
+    /// Remaining auto-rematches a player has granted against an opponent
+    pub fn get_auto_rematch(env: Env, player: Address, opponent: Address) -> u32 {
+        env.storage()
+            .persistent()
+            .get(&DataKey::AutoRematch(player, opponent))
+            .unwrap_or(0)
+    }
+    
+    // ========================================================================
+    // TOURNAMENTS
+    // ========================================================================
+    
+    /// Open a single-elimination tournament for `max_players` entrants
+    /// (a power of two, 2..=MAX_TOURNAMENT_PLAYERS) paying `entry_stake` each
+    pub fn create_tournament(
+        env: Env,
+        organizer: Address,
+        entry_stake: i128,
+        token_address: Address,
+        max_players: u32,
+    ) -> u64 {
+        organizer.require_auth();
+        Self::ensure_not_paused(&env);
+        
+        if !(2..=MAX_TOURNAMENT_PLAYERS).contains(&max_players) || !max_players.is_power_of_two() {
+            panic_with_error!(&env, Error::InvalidArgument);
+        }
+        if entry_stake <= 0
+            || entry_stake.checked_mul(max_players as i128).is_none_or(|pool| pool > MAX_POT)
+        {
+            panic_with_error!(&env, Error::InvalidStake);
+        }
+        if !Self::is_token_allowed(env.clone(), token_address.clone()) {
+            panic_with_error!(&env, Error::TokenNotAllowed);
+        }
+        
+        let key = DataKey::TournamentCounter;
+        let tournament_id: u64 = env.storage().persistent().get(&key).unwrap_or(1);
+        env.storage().persistent().set(&key, &(tournament_id + 1));
+        
+        let tournament = Tournament {
+            tournament_id,
+            organizer,
+            token: token_address,
+            entry_stake,
+            max_players,
+            players: Vec::new(&env),
+            state: TournamentState::Registration,
+            round: 0,
+            games: Vec::new(&env),
+            champion: None,
+            commit_window: BRACKET_COMMIT_WINDOW,
+            reveal_window: BRACKET_REVEAL_WINDOW,
+        };
+        env.storage().persistent().set(&DataKey::Tournament(tournament_id), &tournament);
+        
+        tournament_id
+    }
+    
+    /// Enter a tournament that is still registering, paying the entry stake
+    pub fn join_tournament(env: Env, tournament_id: u64, player: Address, token_address: Address) -> Tournament {
+        player.require_auth();
+        Self::ensure_not_paused(&env);
+        
+        let mut tournament = Self::load_tournament(&env, tournament_id);
+        if tournament.state != TournamentState::Registration {
+            panic_with_error!(&env, Error::InvalidTournamentState);
+        }
+        if token_address != tournament.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        if tournament.players.len() >= tournament.max_players {
+            panic_with_error!(&env, Error::InvalidTournamentState);
+        }
+        if tournament.players.contains(&player) {
+            panic_with_error!(&env, Error::AlreadyJoined);
+        }
+        
+        // First-time entrants get a default profile, as in join_game
+        Self::ensure_registered(&env, &player);
+        
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.transfer(&player, &env.current_contract_address(), &tournament.entry_stake);
+        Self::adjust_tournament_escrow(&env, &token_address, tournament.entry_stake);
+        
+        tournament.players.push_back(player);
+        env.storage().persistent().set(&DataKey::Tournament(tournament_id), &tournament);
+        
+        tournament
+    }
+    
+    /// Start a full bracket: entrants are paired in join order into the
+    /// first round's games (organizer only)
+    pub fn start_tournament(env: Env, tournament_id: u64) -> Tournament {
+        let mut tournament = Self::load_tournament(&env, tournament_id);
+        tournament.organizer.require_auth();
+        Self::ensure_not_paused(&env);
+        
+        if tournament.state != TournamentState::Registration {
+            panic_with_error!(&env, Error::InvalidTournamentState);
+        }
+        if tournament.players.len() != tournament.max_players {
+            panic_with_error!(&env, Error::InvalidTournamentState);
+        }
+        
+        tournament.state = TournamentState::Running;
+        let entrants = tournament.players.clone();
+        Self::open_bracket_round(&env, &mut tournament, &entrants);
+        env.storage().persistent().set(&DataKey::Tournament(tournament_id), &tournament);
+        
+        tournament
+    }
+    
+    /// Move a tournament on once every game of the current round is over
+    /// Anyone may call this. Winners are paired in bracket order into the
+    /// next round; the last one standing is paid the pool less the house fee.
+    /// A bracket game settled without a winner (a refunded timeout or
+    /// arbitration) advances its player one.
+    pub fn advance_tournament(env: Env, tournament_id: u64, token_address: Address) -> Tournament {
+        let mut tournament = Self::load_tournament(&env, tournament_id);
+        if tournament.state != TournamentState::Running {
+            panic_with_error!(&env, Error::InvalidTournamentState);
+        }
+        if token_address != tournament.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        let mut winners = Vec::new(&env);
+        for game_id in tournament.games.iter() {
+            let game = Self::load_game(&env, game_id);
+            if game.state != GameState::Completed {
+                panic_with_error!(&env, Error::InvalidTournamentState);
+            }
+            winners.push_back(game.winner.unwrap_or(game.player_one));
+        }
+        
+        if winners.len() > 1 {
+            Self::open_bracket_round(&env, &mut tournament, &winners);
+        } else {
+            let champion = winners.get_unchecked(0);
+            let pool = tournament.entry_stake * tournament.players.len() as i128;
+            let fee_bps = Self::get_fee_for_token(env.clone(), tournament.token.clone());
+            let fee = pool * fee_bps as i128 / BPS_DENOMINATOR;
+            if fee > 0 {
+                Self::credit_treasury(&env, &tournament.token, fee);
+            }
+            Self::adjust_tournament_escrow(&env, &tournament.token, -pool);
+            let token_client = token::Client::new(&env, &tournament.token);
+            token_client.transfer(&env.current_contract_address(), &champion, &(pool - fee));
+            
+            tournament.state = TournamentState::Finished;
+            tournament.champion = Some(champion.clone());
+            env.events().publish(
+                (EVENT_TOPIC, symbol_short!("champion")),
+                (tournament_id, champion, pool - fee),
+            );
+        }
+        env.storage().persistent().set(&DataKey::Tournament(tournament_id), &tournament);
+        
+        tournament
+    }
+    
+    /// Call off a tournament that hasn't started, refunding every entrant
+    /// (organizer only)
+    pub fn cancel_tournament(env: Env, tournament_id: u64, token_address: Address) -> Tournament {
+        let mut tournament = Self::load_tournament(&env, tournament_id);
+        tournament.organizer.require_auth();
+        if tournament.state != TournamentState::Registration {
+            panic_with_error!(&env, Error::InvalidTournamentState);
+        }
+        if token_address != tournament.token {
+            panic_with_error!(&env, Error::TokenMismatch);
+        }
+        
+        let token_client = token::Client::new(&env, &tournament.token);
+        for player in tournament.players.iter() {
+            token_client.transfer(&env.current_contract_address(), &player, &tournament.entry_stake);
+        }
+        let refunded = tournament.entry_stake * tournament.players.len() as i128;
+        Self::adjust_tournament_escrow(&env, &tournament.token, -refunded);
+        
+        tournament.state = TournamentState::Cancelled;
+        env.storage().persistent().set(&DataKey::Tournament(tournament_id), &tournament);
+        
+        tournament
+    }
+    
+    /// Retrieve a tournament and its current bracket round
+    pub fn get_tournament(env: Env, tournament_id: u64) -> Option<Tournament> {
+        env.storage().persistent().get(&DataKey::Tournament(tournament_id))
+    }
+    
+    // ========================================================================
+    // OPERATOR TOOLS
+    // ========================================================================
+    
+    /// Active games that need attention, with the reason, up to `max` entries
+    /// Only the first MAINTENANCE_SCAN active games are inspected. A disputed
+    /// game is reported as Disputed whatever else is going on with it.
+    pub fn get_stuck_games(env: Env, max: u32) -> Vec<(u64, StuckReason)> {
+        let mut stuck = Vec::new(&env);
+        let now = env.ledger().sequence();
+        
+        for game_id in Self::get_active_games(env.clone()).iter().take(MAINTENANCE_SCAN as usize) {
+            if stuck.len() >= max {
+                break;
+            }
+            let game: Game = match env.storage().persistent().get(&DataKey::Game(game_id)) {
+                Some(game) => game,
+                None => continue,
+            };
+            
+            let disputed = Self::get_dispute(env.clone(), game_id)
+                .is_some_and(|dispute| dispute.resolution == DisputeResolution::Unresolved);
+            let reason = if disputed {
+                Some(StuckReason::Disputed)
+            } else if game.state == GameState::WaitingForPlayer {
+                let seated = game.player_two.is_some();
+                (seated && game.commit_window > 0 && now > game.commit_deadline)
+                    .then_some(StuckReason::CommitOverdue)
+            } else if game.state != GameState::MovesCommitted {
+                None
+            } else if game.p1_move > 0 && game.p2_move > 0 {
+                Some(StuckReason::ReadyToFinalize)
+            } else if game.reveal_window > 0 && now > game.reveal_deadline {
+                Some(StuckReason::RevealOverdue)
+            } else {
+                None
+            };
+            
+            if let Some(reason) = reason {
+                stuck.push_back((game_id, reason));
+            }
+        }
+        
+        stuck
+    }
+    
+    /// The contract's own balance of a token
+    pub fn get_contract_balance(env: Env, token_address: Address) -> i128 {
+        let token_client = token::Client::new(&env, &token_address);
+        token_client.balance(&env.current_contract_address())
+    }
+    
+    /// Stakes currently escrowed by unfinished games in a token
+    /// Scans the whole active list, so this is meant for off-chain simulation.
+    pub fn get_total_locked(env: Env, token_address: Address) -> i128 {
+        let mut locked: i128 = 0;
+        for game_id in Self::get_active_games(env.clone()).iter() {
+            let game = Self::load_game(&env, game_id);
+            if game.token == token_address {
+                locked += Self::escrowed(&env, &game);
+            }
+        }
+        locked
+    }
+    
+    /// Compare what the contract should hold in a token against what it holds
+    /// Returns (expected_escrow, actual_balance). Expected is the stakes of every
+    /// unfinished game in that token plus internal balances plus the treasury.
+    /// A mismatch signals an accounting bug or stray funds sent to the contract.
+    /// Scans the whole active list, so this is meant for off-chain simulation.
+    pub fn reconcile(env: Env, token_address: Address) -> (i128, i128) {
+        let mut expected = Self::get_total_locked(env.clone(), token_address.clone());
+        
+        expected += env.storage()
+            .persistent()
+            .get::<DataKey, i128>(&DataKey::TournamentEscrow(token_address.clone()))
+            .unwrap_or(0);
+        expected += env.storage()
+            .persistent()
+            .get::<DataKey, i128>(&DataKey::BalanceTotal(token_address.clone()))
+            .unwrap_or(0);
+        expected += Self::get_treasury(env.clone(), token_address.clone());
+        expected += Self::get_rewards_pool(env.clone(), token_address.clone());
+        expected += env.storage()
+            .persistent()
+            .get::<DataKey, i128>(&DataKey::BetEscrow(token_address.clone()))
+            .unwrap_or(0);
+        
+        (expected, Self::get_contract_balance(env, token_address))
+    }
+    
+    // ========================================================================
+    // HELPER FUNCTIONS (PRIVATE LOGIC)
+    // ========================================================================
+    
+    /// Contract holdings in a token not yet owned by a player balance, the
+    /// treasury or the rewards pool: escrowed stakes, bets and entries
+    fn unallocated(env: &Env, token: &Address) -> i128 {
+        let held = token::Client::new(env, token).balance(&env.current_contract_address());
+        let balances: i128 = env.storage()
+            .persistent()
+            .get(&DataKey::BalanceTotal(token.clone()))
+            .unwrap_or(0);
+        held - balances
+            - Self::get_treasury(env.clone(), token.clone())
+            - Self::get_rewards_pool(env.clone(), token.clone())
+    }
+    
+    /// Pay a participant's completion bonus out of the rewards pool
+    /// Once the pool can't cover the full bonus it pays out whatever is left
+    /// Returns the bonus actually paid
+    fn pay_completion_bonus(env: &Env, game: &Game, player: &Address) -> i128 {
+        let reward_bps = Self::get_reward_bps(env.clone());
+        if reward_bps == 0 {
+            return 0;
+        }
+        
+        let key = DataKey::RewardsPool(game.token.clone());
+        let pool: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        let bonus = (game.stake_amount * reward_bps as i128 / BPS_DENOMINATOR).min(pool);
+        if bonus <= 0 {
+            return 0;
+        }
+        
+        env.storage().persistent().set(&key, &(pool - bonus));
+        Self::pay_out(env, game, player, bonus);
+        bonus
+    }
+    
+    /// Bets are only accepted while both moves are sealed and none is revealed
+    fn assert_betting_open(env: &Env, game: &Game) {
+        if game.state != GameState::MovesCommitted || game.p1_move > 0 || game.p2_move > 0 {
+            panic_with_error!(env, Error::BettingClosed);
+        }
+    }
+    
+    /// Keep the per-token sum of unsettled bets in step with bets and payouts
+    fn adjust_bet_escrow(env: &Env, token: &Address, delta: i128) {
+        let key = DataKey::BetEscrow(token.clone());
+        let total: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(total + delta));
+    }
+    
+    /// Keep an active game's storage from being archived (see GAME_TTL_THRESHOLD)
+    fn extend_game_ttl(env: &Env, game: &Game) {
+        let storage = env.storage().persistent();
+        storage.extend_ttl(&DataKey::Game(game.game_id), GAME_TTL_THRESHOLD, GAME_TTL_EXTEND_TO);
+        storage.extend_ttl(&DataKey::ActiveGames, GAME_TTL_THRESHOLD, GAME_TTL_EXTEND_TO);
+        let mut players = Vec::from_array(env, [game.player_one.clone()]);
+        if let Some(p2) = &game.player_two {
+            players.push_back(p2.clone());
+        }
+        for player in players.iter() {
+            storage.extend_ttl(&DataKey::Player(player), GAME_TTL_THRESHOLD, GAME_TTL_EXTEND_TO);
+        }
+        env.storage().instance().extend_ttl(GAME_TTL_THRESHOLD, GAME_TTL_EXTEND_TO);
+    }
+    
+    /// Fail with Error::Paused while the admin has paused new games
+    fn ensure_not_paused(env: &Env) {
+        if Self::is_paused(env.clone()) {
+            panic_with_error!(env, Error::Paused);
+        }
+    }
+    
+    fn load_game(env: &Env, game_id: u64) -> Game {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Game(game_id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::GameNotFound))
+    }
+    
+    /// Load the admin and require its authorization
+    fn require_admin(env: &Env) -> Address {
+        let admin: Address = env.storage()
+            .persistent()
+            .get(&DataKey::Admin)
+            .unwrap_or_else(|| panic_with_error!(env, Error::NotInitialized));
+        admin.require_auth();
+        admin
+    }
+    
+    /// Add collected fees to the per-token treasury balance
+    fn credit_treasury(env: &Env, token: &Address, amount: i128) {
+        let key = DataKey::Treasury(token.clone());
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(balance + amount));
+    }
+    
+    /// Calculate the commitment hash from game ID, move and salt
+    /// This is the same calculation players do off-chain
+    fn calculate_commitment(env: &Env, game_id: u64, move_id: u32, salt: BytesN<32>) -> BytesN<32> {
+        // Convert game_id to 8 bytes and move_id to 4 bytes (big-endian)
+        let game_bytes = game_id.to_be_bytes();
+        let move_bytes = move_id.to_be_bytes();
+        
+        // Create 44-byte array: 8 bytes (game) + 4 bytes (move) + 32 bytes (salt)
+        let mut data = [0u8; 44];
+        data[..8].copy_from_slice(&game_bytes);
+        data[8..12].copy_from_slice(&move_bytes);
+        data[12..].copy_from_slice(salt.to_array().as_ref());
+        
+        // Hash the concatenated data
+        let bytes = soroban_sdk::Bytes::from_array(env, &data);
+        env.crypto().sha256(&bytes).into()
+    }
+    
+    /// Game logic: determine winner based on moves
+    /// Returns Some(Address) for winner, None for draw
+    fn determine_winner(game: &Game, p1_move: u32, p2_move: u32) -> Option<Address> {
+        if p1_move == p2_move {
+            return None; // Draw
+        }
+        
+        // Attack (1) beats Defense (2), Defense (2) beats Magic (3), Magic (3) beats Attack (1)
+        // The 5-move set adds Lizard (4) and Spock (5), each beating two moves
+        let p1_wins = matches!(
+            (p1_move, p2_move),
+            (1, 2) | (2, 3) | (3, 1)   // Classic cycle
+                | (1, 4) | (2, 4)      // Attack and Defense beat Lizard
+                | (3, 5) | (4, 5)      // Magic and Lizard beat Spock
+                | (4, 3)               // Lizard beats Magic
+                | (5, 1) | (5, 2)      // Spock beats Attack and Defense
+        );
+        
+        if p1_wins {
+            Some(game.player_one.clone())
+        } else {
+            game.player_two.clone()
+        }
+    }
+    
+    /// Decide a drawn game for DrawPolicy::HashTiebreak
+    /// Each player's score is SHA256(own commitment || tiebreak seed), where the
+    /// seed is the XOR of both revealed salts; the lexicographically greater
+    /// score wins. This is fully deterministic given the revealed values, yet
+    /// nobody can predict it before revealing: the seed needs the opponent's
+    /// salt, which stays hidden behind their commitment until reveal.
+    fn tiebreak_by_hash(env: &Env, game: &Game) -> Address {
+        let score = |commitment: &BytesN<32>| -> BytesN<32> {
+            let mut data = [0u8; 64];
+            data[..32].copy_from_slice(commitment.to_array().as_ref());
+            data[32..].copy_from_slice(game.tiebreak_seed.to_array().as_ref());
+            env.crypto().sha256(&soroban_sdk::Bytes::from_array(env, &data)).into()
+        };
+        
+        if score(&game.p1_commitment) > score(&game.p2_commitment) {
+            game.player_one.clone()
+        } else {
+            game.player_two.clone().unwrap()
+        }
+    }
+    
+    /// Decide a drawn game for DrawPolicy::SuddenDeathRandom
+    /// Flips a coin on the low bit of SHA256(p1 commitment || p2 commitment ||
+    /// ledger sequence): 0 picks player one, 1 player two.
+    /// This is only WEAKLY random and NOT cryptographically secure. Whoever
+    /// submits finalize_game chooses the ledger it lands in, and validators
+    /// can see the inputs beforehand, so the flip can be steered by retrying.
+    /// Good enough for casual games; don't use it for high stakes.
+    fn tiebreak_by_ledger(env: &Env, game: &Game) -> Address {
+        let mut data = [0u8; 68];
+        data[..32].copy_from_slice(game.p1_commitment.to_array().as_ref());
+        data[32..64].copy_from_slice(game.p2_commitment.to_array().as_ref());
+        data[64..].copy_from_slice(&env.ledger().sequence().to_be_bytes());
+        let digest = env.crypto().sha256(&soroban_sdk::Bytes::from_array(env, &data)).to_array();
+        
+        if digest[31] & 1 == 0 {
+            game.player_one.clone()
+        } else {
+            game.player_two.clone().unwrap()
+        }
+    }
+    
+    /// Byte-wise XOR of two 32-byte values
+    fn xor_bytes(env: &Env, a: &BytesN<32>, b: &BytesN<32>) -> BytesN<32> {
+        let mut out = a.to_array();
+        for (byte, other) in out.iter_mut().zip(b.to_array().iter()) {
+            *byte ^= other;
+        }
+        BytesN::from_array(env, &out)
+    }
+    
+    /// Whether commit status is currently hidden (hide_progress and not both committed)
+    fn progress_hidden(env: &Env, game: &Game) -> bool {
+        let zero_commitment = BytesN::from_array(env, &[0u8; 32]);
+        game.hide_progress
+            && (game.p1_commitment == zero_commitment || game.p2_commitment == zero_commitment)
+    }
+    
+    /// Zero the per-player commit ledgers, which would otherwise reveal who committed
+    fn mask_commit_timings(timings: &mut PhaseTimings) {
+        timings.p1_committed = 0;
+        timings.p2_committed = 0;
+    }
+    
+    /// Build a fresh game waiting for its second player
+    fn new_game(
+        env: &Env,
+        game_id: u64,
+        player_one: Address,
+        stake_amount: i128,
+        token: Address,
+        rounds: u32,
+    ) -> Game {
+        Game {
+            game_id,
+            player_one,
+            player_two: None,
+            stake_amount,
+            p2_stake: stake_amount,
+            state: GameState::WaitingForPlayer,
+            p1_commitment: BytesN::from_array(env, &[0u8; 32]),
+            p2_commitment: BytesN::from_array(env, &[0u8; 32]),
+            p1_move: 0,
+            p2_move: 0,
+            p1_salt: BytesN::from_array(env, &[0u8; 32]),
+            p2_salt: BytesN::from_array(env, &[0u8; 32]),
+            winner: None,
+            token,
+            rounds,
+            current_round: 1,
+            p1_round_wins: 0,
+            p2_round_wins: 0,
+            settle_internal: false,
+            next_game_id: None,
+            hide_progress: false,
+            move_count: DEFAULT_MOVE_COUNT,
+            outcome: GameOutcome::Pending,
+            commit_window: 0,
+            commit_deadline: 0,
+            reveal_window: 0,
+            reveal_deadline: 0,
+            moderated: false,
+            draw_policy: DrawPolicy::Refund,
+            tiebreak_seed: BytesN::from_array(env, &[0u8; 32]),
+            reveal_order_enforced: false,
+            round_stakes: false,
+            pot: 0,
+            timings: PhaseTimings {
+                created: env.ledger().sequence(),
+                joined: 0,
+                p1_committed: 0,
+                p2_committed: 0,
+                committed: 0,
+                p1_revealed: 0,
+                p2_revealed: 0,
+                completed: 0,
+            },
+            sponsor: None,
+            invited: None,
+            continuation_offered: false,
+            reveal_penalty_bps: 0,
+        }
+    }
+    
+    /// Settle a finished match: pay the pot (less the fee) to the winner or
+    /// split it on a draw, update stats and bonuses, retire the game from the
+    /// active list and chain any auto-rematch. The caller persists the game.
+    fn complete_game(env: &Env, game: &mut Game, winner_addr: Option<Address>) {
+        game.winner = winner_addr.clone();
+        game.state = GameState::Completed;
+        game.timings.completed = env.ledger().sequence();
+        game.outcome = if winner_addr.is_some() {
+            GameOutcome::Won
+        } else {
+            GameOutcome::Draw
+        };
+        
+        // Distribute prizes
+        let total_pot = Self::escrowed(env, game);
+        if game.round_stakes && total_pot == 0 {
+            // A round_stakes match that never pulled a stake: no payouts,
+            // bonuses, ratings or records (zero-stake bracket games still count)
+            Self::remove_from_active_games(env, game.game_id);
+            Self::record_finalized(env, game, 0);
+            return;
+        }
+        let unallocated_before = Self::unallocated(env, &game.token);
+        
+        if let Some(winner) = &winner_addr {
+            // House fee is taken at the rate configured for this game's token
+            let fee_bps = Self::get_fee_for_token(env.clone(), game.token.clone());
+            let fee = total_pot * fee_bps as i128 / BPS_DENOMINATOR;
+            if fee > 0 {
+                Self::credit_treasury(env, &game.token, fee);
+                Self::pay_reveal_bonuses(env, game, fee);
+            }
+            
+            // Winner takes the rest of the pot
+            if total_pot - fee > 0 {
+                Self::pay_out(env, game, winner, total_pot - fee);
+            }
+            Self::add_to_totals(env, winner, 0, total_pot - fee);
+            
+            // Update player stats
+            Self::update_player_stats(env, &game.player_one, winner == &game.player_one);
+            let p2 = game.player_two.as_ref().unwrap();
+            Self::update_player_stats(env, p2, winner == p2);
+        } else if game.draw_policy == DrawPolicy::DonateToPool {
+            // Draw - both players agreed up front to fund future rewards
+            let key = DataKey::RewardsPool(game.token.clone());
+            let pool: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+            env.storage().persistent().set(&key, &(pool + total_pot));
+            
+            Self::increment_draws(env, &game.player_one);
+            Self::increment_draws(env, game.player_two.as_ref().unwrap());
+        } else if game.draw_policy == DrawPolicy::SplitPot {
+            // Draw - halve the pot with any odd stroop going to player one
+            let p2 = game.player_two.as_ref().unwrap();
+            let (p1_share, p2_share) = Self::split_pot(total_pot);
+            for (player, share) in [(&game.player_one, p1_share), (p2, p2_share)] {
+                if share > 0 {
+                    Self::pay_out(env, game, player, share);
+                }
+            }
+            
+            Self::increment_draws(env, &game.player_one);
+            Self::increment_draws(env, p2);
+        } else {
+            // Draw - refund both stakes exactly
+            let p2 = game.player_two.as_ref().unwrap();
+            Self::refund_stakes(env, game, total_pot);
+            
+            // Update stats for draw
+            Self::increment_draws(env, &game.player_one);
+            Self::increment_draws(env, p2);
+        }
+        
+        // Completion bonuses from the rewards pool, while it lasts
+        let p2 = game.player_two.clone().unwrap();
+        Self::pay_completion_bonus(env, game, &game.player_one);
+        Self::pay_completion_bonus(env, game, &p2);
+        
+        // Every stroop of the pot must have left escrow for a player, the
+        // treasury or the pool; reveal and completion bonuses only move funds
+        // between those, so they must net out
+        if unallocated_before - Self::unallocated(env, &game.token) != total_pot {
+            panic_with_error!(env, Error::InvalidAmount);
+        }
+        
+        Self::update_ratings(env, game, &winner_addr);
+        Self::record_head_to_head(env, game, &winner_addr);
+        
+        // Remove from active games
+        Self::remove_from_active_games(env, game.game_id);
+        Self::record_finalized(env, game, total_pot);
+        
+        // Chain straight into the next game if both players opted in
+        game.next_game_id = Self::try_auto_rematch(env, game);
+    }
+    
+    /// Give both seated players back what they put into `pot`, returning the
+    /// amount paid. Even stakes split the pot exactly (it may hold several
+    /// rounds' stakes); handicapped games return each player's own stake.
+    fn refund_stakes(env: &Env, game: &Game, pot: i128) -> i128 {
+        let p2 = game.player_two.clone().unwrap();
+        if game.p2_stake == game.stake_amount {
+            let both = Vec::from_array(env, [game.player_one.clone(), p2]);
+            return Self::distribute_exact(env, game, &both, pot);
+        }
+        
+        Self::pay_out(env, game, &game.player_one, game.stake_amount);
+        Self::pay_out(env, game, &p2, game.p2_stake);
+        game.stake_amount + game.p2_stake
+    }
+    
+    /// Shares of a SplitPot draw as (player one, player two)
+    fn split_pot(pot: i128) -> (i128, i128) {
+        (pot - pot / 2, pot / 2)
+    }
+    
+    /// Refund the creator of an unjoined game and retire it as Completed
+    /// with the given outcome, so its ID still resolves in histories
+    fn close_open_game(env: &Env, game: &mut Game, outcome: GameOutcome) {
+        let stake = Self::escrowed(env, game);
+        if stake > 0 {
+            Self::pay_out(env, game, &game.player_one, stake);
+        }
+        
+        game.state = GameState::Completed;
+        game.outcome = outcome;
+        game.timings.completed = env.ledger().sequence();
+        Self::remove_from_active_games(env, game.game_id);
+        env.storage().persistent().set(&DataKey::Game(game.game_id), game);
+        let topic = if outcome == GameOutcome::Expired {
+            symbol_short!("expired")
+        } else {
+            symbol_short!("cancelled")
+        };
+        env.events().publish((EVENT_TOPIC, topic), (game.game_id, stake));
+    }
+    
+    /// Round wins that clinch a best-of-N match (a strict majority)
+    fn wins_needed(game: &Game) -> u32 {
+        game.rounds / 2 + 1
+    }
+    
+    /// Tokens the contract currently holds for a game's players
+    fn escrowed(env: &Env, game: &Game) -> i128 {
+        if game.round_stakes {
+            game.pot
+        } else if game.player_two.is_some() {
+            game.stake_amount
+                .checked_add(game.p2_stake)
+                .unwrap_or_else(|| panic_with_error!(env, Error::InvalidStake))
+        } else {
+            game.stake_amount
+        }
+    }
+    
+    /// Whether this round's stake can be pulled from `player` right now
+    fn can_pull_stake(env: &Env, game: &Game, player: &Address) -> bool {
+        let token_client = token::Client::new(env, &game.token);
+        let contract = env.current_contract_address();
+        token_client.allowance(player, &contract) >= game.stake_amount
+            && token_client.balance(player) >= game.stake_amount
+    }
+    
+    /// Start-of-round funding for round_stakes games
+    /// When both players can cover the stake it is pulled from each. If only
+    /// one can, the other forfeits the round unplayed (nothing is pulled, so
+    /// both have always paid in equally) and the next round is tried. If
+    /// neither can, the match ends on the current score.
+    fn collect_round_stakes(env: &Env, game: &mut Game) {
+        let p1 = game.player_one.clone();
+        let p2 = game.player_two.clone().unwrap_or_else(|| panic_with_error!(env, Error::NoOpponent));
+        let contract = env.current_contract_address();
+        let token_client = token::Client::new(env, &game.token);
+        
+        loop {
+            let p1_funded = Self::can_pull_stake(env, game, &p1);
+            let p2_funded = Self::can_pull_stake(env, game, &p2);
+            
+            let round_winner = match (p1_funded, p2_funded) {
+                (true, true) => {
+                    token_client.transfer_from(&contract, &p1, &contract, &game.stake_amount);
+                    token_client.transfer_from(&contract, &p2, &contract, &game.stake_amount);
+                    Self::add_to_totals(env, &p1, game.stake_amount, 0);
+                    Self::add_to_totals(env, &p2, game.stake_amount, 0);
+                    game.pot = game.stake_amount
+                        .checked_mul(2)
+                        .and_then(|deposit| game.pot.checked_add(deposit))
+                        .unwrap_or_else(|| panic_with_error!(env, Error::InvalidStake));
+                    return;
+                }
+                (false, false) => {
+                    let winner = match game.p1_round_wins.cmp(&game.p2_round_wins) {
+                        core::cmp::Ordering::Greater => Some(p1),
+                        core::cmp::Ordering::Less => Some(p2),
+                        core::cmp::Ordering::Equal => None,
+                    };
+                    Self::complete_game(env, game, winner);
+                    return;
+                }
+                (true, false) => {
+                    game.p1_round_wins += 1;
+                    p1.clone()
+                }
+                (false, true) => {
+                    game.p2_round_wins += 1;
+                    p2.clone()
+                }
+            };
+            
+            Self::record_round(env, game, &Some(round_winner.clone()));
+            let wins_needed = Self::wins_needed(game);
+            if game.p1_round_wins >= wins_needed || game.p2_round_wins >= wins_needed {
+                Self::complete_game(env, game, Some(round_winner));
+                return;
+            }
+            Self::start_next_round(env, game);
+        }
+    }
+    
+    /// Send winnings or refunds to a player
+    /// Games funded from internal balances settle back into those balances
+    fn pay_out(env: &Env, game: &Game, to: &Address, amount: i128) {
+        if game.settle_internal {
+            Self::credit_balance(env, to, &game.token, amount);
+            Self::lock_credit(env, to, &game.token, amount);
+        } else {
+            let token_client = token::Client::new(env, &game.token);
+            token_client.transfer(&env.current_contract_address(), to, &amount);
+        }
+    }
+    
+    /// Split `amount` evenly among `recipients` with no dust left behind
+    /// Any remainder from the division goes to the lowest address, so the
+    /// result is deterministic and the shares always sum to exactly `amount`,
+    /// which is returned as the total paid
+    fn distribute_exact(env: &Env, game: &Game, recipients: &Vec<Address>, amount: i128) -> i128 {
+        let count = recipients.len() as i128;
+        if count == 0 {
+            panic_with_error!(env, Error::InvalidArgument);
+        }
+        
+        let share = amount / count;
+        let remainder = amount % count;
+        
+        let mut lowest = recipients.get_unchecked(0);
+        for recipient in recipients.iter() {
+            if recipient < lowest {
+                lowest = recipient;
+            }
+        }
+        
+        let mut paid = 0;
+        for recipient in recipients.iter() {
+            let payout = if recipient == lowest { share + remainder } else { share };
+            if payout > 0 {
+                Self::pay_out(env, game, &recipient, payout);
+                paid += payout;
+            }
+        }
+        paid
+    }
+    
+    /// Add to a player's internal balance, returning the new balance
+    fn credit_balance(env: &Env, player: &Address, token: &Address, amount: i128) -> i128 {
+        let key = DataKey::Balance(player.clone(), token.clone());
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        let updated = balance + amount;
+        env.storage().persistent().set(&key, &updated);
+        Self::adjust_balance_total(env, token, amount);
+        updated
+    }
+    
+    /// Subtract from a player's internal balance, returning the new balance
+    fn debit_balance(env: &Env, player: &Address, token: &Address, amount: i128) -> i128 {
+        let key = DataKey::Balance(player.clone(), token.clone());
+        let balance: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        if amount > balance {
+            panic_with_error!(env, Error::InsufficientFunds);
+        }
+        let updated = balance - amount;
+        env.storage().persistent().set(&key, &updated);
+        Self::adjust_balance_total(env, token, -amount);
+        updated
+    }
+    
+    /// Record a winnings credit that unlocks after the withdrawal timelock
+    fn lock_credit(env: &Env, player: &Address, token: &Address, amount: i128) {
+        let timelock = Self::get_withdrawal_timelock(env.clone());
+        if timelock == 0 {
+            return;
+        }
+        
+        let key = DataKey::LockedCredits(player.clone(), token.clone());
+        let mut credits: Vec<(i128, u32)> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        credits.push_back((amount, env.ledger().sequence() + timelock));
+        env.storage().persistent().set(&key, &credits);
+    }
+    
+    /// Drop credits whose timelock has passed, returning the amount still locked
+    fn prune_locked_credits(env: &Env, player: &Address, token: &Address) -> i128 {
+        let key = DataKey::LockedCredits(player.clone(), token.clone());
+        let credits: Vec<(i128, u32)> = match env.storage().persistent().get(&key) {
+            Some(credits) => credits,
+            None => return 0,
+        };
+        
+        let now = env.ledger().sequence();
+        let mut pending = Vec::new(env);
+        let mut locked = 0;
+        for (amount, unlock_ledger) in credits.iter() {
+            if unlock_ledger > now {
+                pending.push_back((amount, unlock_ledger));
+                locked += amount;
+            }
+        }
+        
+        if pending.is_empty() {
+            env.storage().persistent().remove(&key);
+        } else {
+            env.storage().persistent().set(&key, &pending);
+        }
+        locked
+    }
+    
+    /// Keep the per-token sum of internal balances in step with credits/debits
+    fn adjust_balance_total(env: &Env, token: &Address, delta: i128) {
+        let key = DataKey::BalanceTotal(token.clone());
+        let total: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(total + delta));
+    }
+    
+    /// Create the next game between the same players when both have
+    /// auto-rematch remaining and enough internal balance to cover the stake,
+    /// and its settings still pass validate_game_config
+    /// Returns the new game's ID, or None if the chain stops here
+    fn try_auto_rematch(env: &Env, game: &Game) -> Option<u64> {
+        let p1 = game.player_one.clone();
+        let p2 = game.player_two.clone()?;
+        if game.stake_amount == 0 {
+            return None; // Tournament bracket games never chain
+        }
+        
+        let p1_key = DataKey::AutoRematch(p1.clone(), p2.clone());
+        let p2_key = DataKey::AutoRematch(p2.clone(), p1.clone());
+        let p1_remaining: u32 = env.storage().persistent().get(&p1_key).unwrap_or(0);
+        let p2_remaining: u32 = env.storage().persistent().get(&p2_key).unwrap_or(0);
+        if p1_remaining == 0 || p2_remaining == 0 {
+            return None;
+        }
+        
+        // The rematch has to pass today's whitelist, stake bounds and lobby cap
+        let config = Self::rematch_config(game, game.stake_amount, game.p2_stake);
+        if Self::is_paused(env.clone()) || Self::validate_game_config(env.clone(), config).is_err() {
+            return None;
+        }
+        
+        let stake = game.stake_amount;
+        let p1_balance = Self::get_balance(env.clone(), p1.clone(), game.token.clone());
+        let p2_balance = Self::get_balance(env.clone(), p2.clone(), game.token.clone());
+        if p1_balance < stake || p2_balance < game.p2_stake {
+            return None;
+        }
+        
+        Self::debit_balance(env, &p1, &game.token, stake);
+        Self::debit_balance(env, &p2, &game.token, game.p2_stake);
+        Self::add_to_totals(env, &p1, stake, 0);
+        Self::add_to_totals(env, &p2, game.p2_stake, 0);
+        env.storage().persistent().set(&p1_key, &(p1_remaining - 1));
+        env.storage().persistent().set(&p2_key, &(p2_remaining - 1));
+        
+        let game_id = Self::get_and_increment_counter(env);
+        Self::add_to_player_games(env, &p1, game_id);
+        Self::add_to_player_games(env, &p2, game_id);
+        let mut rematch = Self::new_game(env, game_id, p1, stake, game.token.clone(), game.rounds);
+        rematch.player_two = Some(p2);
+        rematch.p2_stake = game.p2_stake;
+        rematch.settle_internal = true;
+        rematch.hide_progress = game.hide_progress;
+        rematch.move_count = game.move_count;
+        rematch.commit_window = game.commit_window;
+        rematch.reveal_window = game.reveal_window;
+        rematch.draw_policy = game.draw_policy;
+        rematch.reveal_order_enforced = game.reveal_order_enforced;
+        rematch.reveal_penalty_bps = game.reveal_penalty_bps;
+        Self::start_commit_clock(env, &mut rematch);
+        
+        env.storage().persistent().set(&DataKey::Game(game_id), &rematch);
+        Self::add_to_active_games(env, game_id);
+        env.events().publish(
+            (EVENT_TOPIC, symbol_short!("created")),
+            (game_id, rematch.player_one.clone(), stake),
+        );
+        env.events().publish(
+            (EVENT_TOPIC, symbol_short!("joined")),
+            (game_id, rematch.player_two.clone().unwrap()),
+        );
+        
+        Some(game_id)
+    }
+    
+    /// The settings of `game` as a config for its rematch, with the creator
+    /// staking `stake` and the opponent `opponent_stake`
+    fn rematch_config(game: &Game, stake: i128, opponent_stake: i128) -> GameConfig {
+        GameConfig {
+            rounds: game.rounds,
+            hide_progress: game.hide_progress,
+            commit_window: game.commit_window,
+            reveal_window: game.reveal_window,
+            draw_policy: game.draw_policy,
+            reveal_order_enforced: game.reveal_order_enforced,
+            reveal_penalty_bps: game.reveal_penalty_bps,
+            round_stakes: game.round_stakes,
+            ruleset: if game.move_count == EXTENDED_MOVE_COUNT { Ruleset::Extended } else { Ruleset::Classic },
+            use_balance: game.settle_internal,
+            p2_stake: (opponent_stake != stake).then_some(opponent_stake),
+            ..GameConfig::new(stake, &game.token)
+        }
+    }
+    
+    fn load_tournament(env: &Env, tournament_id: u64) -> Tournament {
+        env.storage()
+            .persistent()
+            .get(&DataKey::Tournament(tournament_id))
+            .unwrap_or_else(|| panic_with_error!(env, Error::TournamentNotFound))
+    }
+    
+    /// Keep the per-token sum of tournament entry stakes in step
+    fn adjust_tournament_escrow(env: &Env, token: &Address, delta: i128) {
+        let key = DataKey::TournamentEscrow(token.clone());
+        let total: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+        env.storage().persistent().set(&key, &(total + delta));
+    }
+    
+    /// Seat `players` pairwise (first vs second, third vs fourth, ...) in
+    /// the next bracket round's games
+    /// Bracket games carry no stake and replay draws (SuddenDeath), so every
+    /// game produces a winner to advance.
+    fn open_bracket_round(env: &Env, tournament: &mut Tournament, players: &Vec<Address>) {
+        let mut games = Vec::new(env);
+        for i in (0..players.len()).step_by(2) {
+            let p1 = players.get_unchecked(i);
+            let p2 = players.get_unchecked(i + 1);
+            let game_id = Self::get_and_increment_counter(env);
+            
+            let mut game = Self::new_game(env, game_id, p1.clone(), 0, tournament.token.clone(), 1);
+            game.player_two = Some(p2.clone());
+            game.move_count = Self::get_move_count(env.clone());
+            game.draw_policy = DrawPolicy::SuddenDeath;
+            game.commit_window = tournament.commit_window;
+            game.reveal_window = tournament.reveal_window;
+            game.timings.joined = env.ledger().sequence();
+            Self::start_commit_clock(env, &mut game);
+            
+            env.storage().persistent().set(&DataKey::Game(game_id), &game);
+            Self::add_to_active_games(env, game_id);
+            Self::add_to_player_games(env, &p1, game_id);
+            Self::add_to_player_games(env, &p2, game_id);
+            Self::extend_game_ttl(env, &game);
+            env.events().publish((EVENT_TOPIC, symbol_short!("created")), (game_id, p1, 0i128));
+            env.events().publish((EVENT_TOPIC, symbol_short!("joined")), (game_id, p2));
+            games.push_back(game_id);
+        }
+        
+        tournament.round += 1;
+        tournament.games = games;
+    }
+    
+    /// Append the round that was just resolved to the game's history
+    fn record_round(env: &Env, game: &Game, winner: &Option<Address>) {
+        let key = DataKey::RoundHistory(game.game_id);
+        let mut history: Vec<RoundRecord> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        
+        history.push_back(RoundRecord {
+            round: game.current_round,
+            p1_move: game.p1_move,
+            p2_move: game.p2_move,
+            p1_commitment: game.p1_commitment.clone(),
+            p2_commitment: game.p2_commitment.clone(),
+            p1_salt: game.p1_salt.clone(),
+            p2_salt: game.p2_salt.clone(),
+            winner: winner.clone(),
+            ledger: env.ledger().sequence(),
+            timestamp: env.ledger().timestamp(),
+        });
+        
+        env.storage().persistent().set(&key, &history);
+    }
+    
+    /// Tally a revealed move on the player's profile
+    fn count_move(env: &Env, player: &Address, move_choice: u32) {
+        let key = DataKey::Player(player.clone());
+        let mut profile: Player = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PlayerNotRegistered));
+        
+        let index = move_choice - 1;
+        let count = profile.move_counts.get(index).unwrap_or(0);
+        profile.move_counts.set(index, count + 1);
+        env.storage().persistent().set(&key, &profile);
+    }
+    
+    /// Count a decided round towards the player's record with that move,
+    /// keeping entries ordered by move
+    fn record_move_result(env: &Env, player: &Address, move_choice: u32, won: bool) {
+        let key = DataKey::MovePerformance(player.clone());
+        let mut tallies: Vec<(u32, u32, u32)> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        
+        let mut index = tallies.len();
+        for (i, (played, _, _)) in tallies.iter().enumerate() {
+            if played >= move_choice {
+                index = i as u32;
+                break;
+            }
+        }
+        
+        let (mut wins, mut losses) = match tallies.get(index) {
+            Some((played, wins, losses)) if played == move_choice => {
+                tallies.remove(index);
+                (wins, losses)
+            }
+            _ => (0, 0),
+        };
+        if won {
+            wins += 1;
+        } else {
+            losses += 1;
+        }
+        tallies.insert(index, (move_choice, wins, losses));
+        
+        env.storage().persistent().set(&key, &tallies);
+    }
+    
+    /// Elo win probability for `rating` against `opponent_rating`, in bps
+    fn win_probability_bps(rating: u32, opponent_rating: u32) -> i128 {
+        let lead = (rating as i128 - opponent_rating as i128).abs();
+        let step = (lead / 25) as usize;
+        let favored = if step + 1 >= ELO_EXPECTED_BPS.len() {
+            ELO_EXPECTED_BPS[ELO_EXPECTED_BPS.len() - 1]
+        } else {
+            let low = ELO_EXPECTED_BPS[step];
+            let high = ELO_EXPECTED_BPS[step + 1];
+            low + (high - low) * (lead % 25) / 25
+        };
+        
+        if rating >= opponent_rating {
+            favored
+        } else {
+            BPS_DENOMINATOR - favored
+        }
+    }
+    
+    /// Count a settled game in its players' head-to-head record
+    /// The pair is stored once, under the lower address first, whichever of
+    /// them created the game.
+    fn record_head_to_head(env: &Env, game: &Game, winner: &Option<Address>) {
+        let p1 = game.player_one.clone();
+        let p2 = game.player_two.clone().unwrap();
+        let (low, high) = if p1 <= p2 { (p1, p2) } else { (p2, p1) };
+        
+        let key = DataKey::H2H(low.clone(), high);
+        let (mut low_wins, mut high_wins, mut draws): (u32, u32, u32) =
+            env.storage().persistent().get(&key).unwrap_or((0, 0, 0));
+        match winner {
+            Some(winner) if *winner == low => low_wins += 1,
+            Some(_) => high_wins += 1,
+            None => draws += 1,
+        }
+        env.storage().persistent().set(&key, &(low_wins, high_wins, draws));
+    }
+    
+    /// Move both players' Elo ratings after a finished game
+    /// Player one scores 1, 0.5 or 0 against their expected score; player two
+    /// moves by the opposite amount, so a draw pulls the ratings together.
+    fn update_ratings(env: &Env, game: &Game, winner: &Option<Address>) {
+        let p1_key = DataKey::Player(game.player_one.clone());
+        let p2_key = DataKey::Player(game.player_two.clone().unwrap());
+        let mut p1: Player = env.storage().persistent().get(&p1_key)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PlayerNotRegistered));
+        let mut p2: Player = env.storage().persistent().get(&p2_key)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PlayerNotRegistered));
+        
+        let score_bps = match winner {
+            Some(w) if *w == game.player_one => BPS_DENOMINATOR,
+            Some(_) => 0,
+            None if Self::get_draw_scoring(env.clone()) == DrawScoring::Ignore => return,
+            None => BPS_DENOMINATOR / 2,
+        };
+        let expected_bps = Self::win_probability_bps(p1.rating, p2.rating);
+        let delta = ELO_K_FACTOR * (score_bps - expected_bps) / BPS_DENOMINATOR;
+        
+        p1.rating = (p1.rating as i128 + delta).max(0) as u32;
+        p2.rating = (p2.rating as i128 - delta).max(0) as u32;
+        env.storage().persistent().set(&p1_key, &p1);
+        env.storage().persistent().set(&p2_key, &p2);
+    }
+    
+    /// Store the GameResult of a settled game and announce it:
+    /// (game_id, winner or None for a draw/refund, pot)
+    fn record_finalized(env: &Env, game: &Game, pot: i128) {
+        let result = GameResult {
+            game_id: game.game_id,
+            player_one: game.player_one.clone(),
+            player_two: game.player_two.clone().unwrap(),
+            p1_move: game.p1_move,
+            p2_move: game.p2_move,
+            winner: game.winner.clone(),
+            pot,
+            finalized_ledger: env.ledger().sequence(),
+        };
+        env.storage().persistent().set(&DataKey::GameResult(game.game_id), &result);
+        env.events().publish(
+            (EVENT_TOPIC, symbol_short!("finalized")),
+            (game.game_id, game.winner.clone(), pot),
+        );
+    }
+    
+    /// Both players are seated for a round: start its commit deadline
+    fn start_commit_clock(env: &Env, game: &mut Game) {
+        if game.commit_window > 0 {
+            game.commit_deadline = env.ledger().sequence() + game.commit_window;
+        }
+    }
+    
+    /// End a game without playing it out (missed deadline or forfeit): the
+    /// pot goes to `winner` less `loser_refund`, which goes back to the loser,
+    /// or back to both players when there is no winner. Saves the game.
+    fn settle_early(
+        env: &Env,
+        game: &mut Game,
+        winner: Option<Address>,
+        outcome: GameOutcome,
+        loser_refund: i128,
+    ) {
+        let pot = Self::escrowed(env, game);
+        let p2 = game.player_two.clone().unwrap();
+        match &winner {
+            Some(winner) => {
+                let loser = if *winner == game.player_one { &p2 } else { &game.player_one };
+                if loser_refund > 0 {
+                    Self::pay_out(env, game, loser, loser_refund);
+                }
+                Self::pay_out(env, game, winner, pot - loser_refund);
+                Self::add_to_totals(env, winner, 0, pot - loser_refund);
+                Self::update_player_stats(env, &game.player_one, *winner == game.player_one);
+                Self::update_player_stats(env, &p2, *winner == p2);
+                Self::update_ratings(env, game, &Some(winner.clone()));
+                Self::record_head_to_head(env, game, &Some(winner.clone()));
+            }
+            None => {
+                Self::refund_stakes(env, game, pot);
+            }
+        }
+        
+        game.winner = winner;
+        game.state = GameState::Completed;
+        game.outcome = outcome;
+        game.timings.completed = env.ledger().sequence();
+        Self::remove_from_active_games(env, game.game_id);
+        env.storage().persistent().set(&DataKey::Game(game.game_id), game);
+        Self::record_finalized(env, game, pot);
+    }
+    
+    /// Check that a game's reveal deadline has passed with a reveal missing,
+    /// returning the player who did reveal (None if neither did)
+    fn reveal_timeout_winner(env: &Env, game: &Game, token_address: &Address) -> Option<Address> {
+        if game.state != GameState::MovesCommitted {
+            panic_with_error!(env, Error::InvalidGameState);
+        }
+        if *token_address != game.token {
+            panic_with_error!(env, Error::TokenMismatch);
+        }
+        if game.reveal_window == 0 {
+            panic_with_error!(env, Error::TimeoutNotAvailable);
+        }
+        if env.ledger().sequence() <= game.reveal_deadline {
+            panic_with_error!(env, Error::DeadlineNotReached);
+        }
+        
+        match (game.p1_move > 0, game.p2_move > 0) {
+            (true, false) => Some(game.player_one.clone()),
+            (false, true) => game.player_two.clone(),
+            (false, false) => None,
+            (true, true) => panic_with_error!(env, Error::TimeoutNotAvailable),
+        }
+    }
+    
+    /// Both moves are sealed: open the reveal phase and start its deadline
+    fn enter_reveal_phase(env: &Env, game: &mut Game) {
+        game.state = GameState::MovesCommitted;
+        game.timings.committed = env.ledger().sequence();
+        if game.reveal_window > 0 {
+            game.reveal_deadline = env.ledger().sequence() + game.reveal_window;
+        }
+    }
+    
+    /// Bonus for a reveal made at ledger `revealed_at`: the configured maximum
+    /// scaled by the fraction of the reveal window still remaining
+    /// bonus = max_bonus * (deadline - revealed_at) / window, capped by the treasury.
+    /// Games without a reveal window never earn a bonus.
+    fn early_reveal_bonus(env: &Env, game: &Game, revealed_at: u32) -> i128 {
+        let max_bonus = Self::get_reveal_bonus(env.clone());
+        if max_bonus == 0 || game.reveal_window == 0 {
+            return 0;
+        }
+        
+        let remaining = game.reveal_deadline.saturating_sub(revealed_at);
+        let bonus = max_bonus * remaining as i128 / game.reveal_window as i128;
+        
+        bonus.min(Self::get_treasury(env.clone(), game.token.clone()))
+    }
+    
+    /// Pay both players' early-reveal bonuses for the deciding round out of
+    /// the treasury, each capped at half the `fee` this game just paid in,
+    /// so the bonuses can never exceed what the game contributed
+    fn pay_reveal_bonuses(env: &Env, game: &Game, fee: i128) {
+        let p2 = game.player_two.as_ref().unwrap();
+        for (player, revealed, revealed_at) in [
+            (&game.player_one, game.p1_move > 0, game.timings.p1_revealed),
+            (p2, game.p2_move > 0, game.timings.p2_revealed),
+        ] {
+            if !revealed {
+                continue;
+            }
+            let bonus = Self::early_reveal_bonus(env, game, revealed_at).min(fee / 2);
+            if bonus > 0 {
+                let key = DataKey::Treasury(game.token.clone());
+                let treasury: i128 = env.storage().persistent().get(&key).unwrap_or(0);
+                env.storage().persistent().set(&key, &(treasury - bonus));
+                Self::pay_out(env, game, player, bonus);
+            }
+        }
+    }
+    
+    /// Clear commitments and moves so the players can play the next round
+    fn start_next_round(env: &Env, game: &mut Game) {
+        let zero_commitment = BytesN::from_array(env, &[0u8; 32]);
+        game.p1_commitment = zero_commitment.clone();
+        game.p2_commitment = zero_commitment;
+        game.p1_move = 0;
+        game.p2_move = 0;
+        game.p1_salt = BytesN::from_array(env, &[0u8; 32]);
+        game.p2_salt = BytesN::from_array(env, &[0u8; 32]);
+        game.tiebreak_seed = BytesN::from_array(env, &[0u8; 32]);
+        game.timings.p1_revealed = 0;
+        game.timings.p2_revealed = 0;
+        game.current_round += 1;
+        game.state = GameState::WaitingForPlayer;
+        Self::start_commit_clock(env, game);
+    }
+    
+    /// Load a player's profile, creating a fresh one if they have none
+    fn ensure_registered(env: &Env, player: &Address) -> Player {
+        let key = DataKey::Player(player.clone());
+        
+        // Check if player already exists in storage
+        if let Some(existing_player) = env.storage().persistent().get::<DataKey, Player>(&key) {
+            return existing_player;
+        }
+        
+        // Create new player profile
+        let new_player = Player {
+            address: player.clone(),
+            wins: 0,
+            losses: 0,
+            draws: 0,
+            notification_prefs: 0,
+            rating: DEFAULT_RATING,
+            name: String::from_str(env, ""),
+            move_counts: Vec::from_array(env, [0u32; EXTENDED_MOVE_COUNT as usize]),
+            current_win_streak: 0,
+            best_win_streak: 0,
+            total_staked: 0,
+            total_won: 0,
+        };
+        
+        // Store in persistent storage (survives contract upgrades)
+        env.storage().persistent().set(&key, &new_player);
+        
+        new_player
+    }
+    
+    /// Add to a player's lifetime stake and winnings totals
+    fn add_to_totals(env: &Env, player_addr: &Address, staked: i128, won: i128) {
+        let key = DataKey::Player(player_addr.clone());
+        let mut player: Player = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PlayerNotRegistered));
+        
+        player.total_staked = player.total_staked
+            .checked_add(staked)
+            .unwrap_or_else(|| panic_with_error!(env, Error::InvalidAmount));
+        player.total_won = player.total_won
+            .checked_add(won)
+            .unwrap_or_else(|| panic_with_error!(env, Error::InvalidAmount));
+        env.storage().persistent().set(&key, &player);
+    }
+    
+    /// Update player win/loss statistics
+    fn update_player_stats(env: &Env, player_addr: &Address, won: bool) {
+        let key = DataKey::Player(player_addr.clone());
+        let mut player: Player = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PlayerNotRegistered));
+        
+        if won {
+            player.wins += 1;
+            player.current_win_streak += 1;
+            player.best_win_streak = player.best_win_streak.max(player.current_win_streak);
+            
+            let stats = Self::update_season_stats(env, player_addr, |stats| stats.wins += 1);
+            Self::leaderboard_insert(env, player_addr, Self::leaderboard_score(env, &stats));
+        } else {
+            player.losses += 1;
+            player.current_win_streak = 0;
+            Self::update_season_stats(env, player_addr, |stats| stats.losses += 1);
+        }
+        
+        env.storage().persistent().set(&key, &player);
+    }
+    
+    /// Place a player on the capped leaderboard, kept sorted by wins (descending)
+    /// A player already on the board is moved to their new position. When the
+    /// board is full a newcomer must strictly beat last place, who is evicted;
+    /// on equal wins the player who got there first keeps the higher spot.
+    fn leaderboard_insert(env: &Env, player: &Address, wins: u32) {
+        let mut board: Vec<(Address, u32)> = env.storage()
+            .persistent()
+            .get(&DataKey::Leaderboard)
+            .unwrap_or(Vec::new(env));
+        
+        if let Some(index) = board.iter().position(|(addr, _)| addr == *player) {
+            board.remove(index as u32);
+        }
+        
+        if board.len() >= LEADERBOARD_SIZE {
+            let (_, cutoff) = board.get_unchecked(LEADERBOARD_SIZE - 1);
+            if wins <= cutoff {
+                return; // Doesn't qualify; the board is unchanged
+            }
+            board.pop_back();
+        }
+        
+        let position = board
+            .iter()
+            .position(|(_, entry_wins)| entry_wins < wins)
+            .map(|index| index as u32)
+            .unwrap_or(board.len());
+        board.insert(position, (player.clone(), wins));
+        
+        env.storage().persistent().set(&DataKey::Leaderboard, &board);
+    }
+    
+    /// Increment draw count for player
+    fn increment_draws(env: &Env, player_addr: &Address) {
+        let key = DataKey::Player(player_addr.clone());
+        let mut player: Player = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or_else(|| panic_with_error!(env, Error::PlayerNotRegistered));
+        
+        player.draws += 1;
+        if Self::get_draws_reset_streaks(env.clone()) {
+            player.current_win_streak = 0;
+        }
+        
+        env.storage().persistent().set(&key, &player);
+        let stats = Self::update_season_stats(env, player_addr, |stats| stats.draws += 1);
+        
+        if Self::get_draw_scoring(env.clone()) == DrawScoring::HalfWin {
+            Self::leaderboard_insert(env, player_addr, Self::leaderboard_score(env, &stats));
+        }
+    }
+    
+    /// A player's leaderboard score for their season stats under the draw scoring rule
+    fn leaderboard_score(env: &Env, stats: &SeasonStats) -> u32 {
+        if Self::get_draw_scoring(env.clone()) != DrawScoring::HalfWin {
+            return stats.wins;
+        }
+        2 * stats.wins + stats.draws
+    }
+    
+    /// Apply a result to the player's stats for the running season, returning them
+    fn update_season_stats(
+        env: &Env,
+        player_addr: &Address,
+        update: impl FnOnce(&mut SeasonStats),
+    ) -> SeasonStats {
+        let season = Self::get_current_season(env.clone());
+        let key = DataKey::PlayerSeason(player_addr.clone(), season);
+        let mut stats: SeasonStats = env.storage().persistent().get(&key).unwrap_or_default();
+        update(&mut stats);
+        env.storage().persistent().set(&key, &stats);
+        stats
+    }
+    
+    /// Get and increment the game counter (atomic operation)
+    /// If the counter was lost or left behind (e.g. archived, or restored from
+    /// an old snapshot) it skips past IDs that already hold a game, so an
+    /// existing game is never overwritten
+    fn get_and_increment_counter(env: &Env) -> u64 {
+        let key = DataKey::GameCounter;
+        let mut counter: u64 = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(1);
+        
+        while env.storage().persistent().has(&DataKey::Game(counter)) {
+            counter += 1;
+        }
+        
+        env.storage().persistent().set(&key, &(counter + 1));
+        
+        counter
+    }
+    
+    /// Add game to active games list
+    fn add_to_active_games(env: &Env, game_id: u64) {
+        let key = DataKey::ActiveGames;
+        let mut active: Vec<u64> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        
+        active.push_back(game_id);
+        env.storage().persistent().set(&key, &active);
+    }
+    
+    /// Add a revealed salt to the player's history when the check is on,
+    /// failing with Error::SaltReused if it is already there
+    fn record_salt(env: &Env, player: &Address, salt: &BytesN<32>) {
+        let limit = Self::get_salt_history(env.clone());
+        if limit == 0 {
+            return;
+        }
+        
+        let key = DataKey::RecentSalts(player.clone());
+        let mut salts: Vec<BytesN<32>> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        if salts.contains(salt) {
+            panic_with_error!(env, Error::SaltReused);
+        }
+        
+        salts.push_back(salt.clone());
+        while salts.len() > limit {
+            salts.pop_front();
+        }
+        env.storage().persistent().set(&key, &salts);
+    }
+    
+    /// Record a game in the player's history index, keeping only the most
+    /// recent PLAYER_GAMES_LIMIT entries
+    fn add_to_player_games(env: &Env, player: &Address, game_id: u64) {
+        let key = DataKey::PlayerGames(player.clone());
+        let mut games: Vec<u64> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        
+        games.push_back(game_id);
+        if games.len() > PLAYER_GAMES_LIMIT {
+            games.pop_front();
+        }
+        env.storage().persistent().set(&key, &games);
+    }
+    
+    /// Remove game from active games list
+    fn remove_from_active_games(env: &Env, game_id: u64) {
+        let key = DataKey::ActiveGames;
+        let active: Vec<u64> = env.storage()
+            .persistent()
+            .get(&key)
+            .unwrap_or(Vec::new(env));
+        
+        // Filter out the game_id - rebuild vec without it
+        let mut filtered = Vec::new(env);
+        for id in active.iter() {
+            if id != game_id {
+                filtered.push_back(id);
+            }
+        }
+        
+        env.storage().persistent().set(&key, &filtered);
+    }
+}
+
+// ============================================================================
+// TESTS
+// ============================================================================
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+    
+    use super::*;
+    use soroban_sdk::{
+        testutils::{Address as _, Events as _, Ledger},
+        token::StellarAssetClient,
+        Address, Env, IntoVal,
+    };
+
+    /// Register the contract and return a client for it
+    fn setup_contract(env: &Env) -> StellarDuelsContractClient<'static> {
+        let contract_id = env.register_contract(None, StellarDuelsContract);
+        StellarDuelsContractClient::new(env, &contract_id)
+    }
+    
+    /// Deploy a test token and return its address
+    fn create_token(env: &Env) -> Address {
+        let issuer = Address::generate(env);
+        env.register_stellar_asset_contract_v2(issuer).address()
+    }
+    
+    /// Generate a registered player holding `balance` of the given token
+    fn funded_player(
+        env: &Env,
+        client: &StellarDuelsContractClient,
+        token: &Address,
+        balance: i128,
+    ) -> Address {
+        let player = Address::generate(env);
+        StellarAssetClient::new(env, token).mint(&player, &balance);
+        client.register_player(&player);
+        player
+    }
+    
+    /// Play a full game (create, join, commit, reveal, finalize) and return its ID
+    fn play_game(
+        client: &StellarDuelsContractClient,
+        token: &Address,
+        p1: &Address,
+        p2: &Address,
+        stake: i128,
+        p1_move: u32,
+        p2_move: u32,
+    ) -> u64 {
+        let game_id = client.create_game(p1, &GameConfig::new(stake, token));
+        client.join_game(&game_id, p2, token);
+        play_round(client, game_id, token, p1, p2, p1_move, p2_move);
+        
+        game_id
+    }
+    
+    /// Commit, reveal, and finalize one round of an already-joined game
+    fn play_round(
+        client: &StellarDuelsContractClient,
+        game_id: u64,
+        token: &Address,
+        p1: &Address,
+        p2: &Address,
+        p1_move: u32,
+        p2_move: u32,
+    ) -> Game {
+        let env = &client.env;
+        let p1_salt = BytesN::from_array(env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(env, &[2u8; 32]);
+        client.commit_move(
+            &game_id,
+            p1,
+            &StellarDuelsContract::calculate_commitment(env, game_id, p1_move, p1_salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            p2,
+            &StellarDuelsContract::calculate_commitment(env, game_id, p2_move, p2_salt.clone()),
+        );
+        client.reveal_move(&game_id, p1, &p1_move, &p1_salt);
+        client.reveal_move(&game_id, p2, &p2_move, &p2_salt);
+        client.finalize_game(&game_id, token)
+    }
+
+    #[test]
+    fn test_player_registration() {
+        let env = Env::default();
+        env.mock_all_auths();  // Mock authorization for testing
+        
+        let contract_id = env.register_contract(None, StellarDuelsContract);
+        let client = StellarDuelsContractClient::new(&env, &contract_id);
+        
+        let player = Address::generate(&env);
+        
+        let registered = client.register_player(&player);
+        assert_eq!(registered.wins, 0);
+        assert_eq!(registered.losses, 0);
+        assert_eq!(registered.draws, 0);
+    }
+    
+    #[test]
+    fn test_commitment_calculation() {
+        let env = Env::default();
+        
+        let move_id: u32 = 1; // Attack
+        let salt = BytesN::from_array(&env, &[42u8; 32]);
+        
+        let commitment = StellarDuelsContract::calculate_commitment(&env, 1, move_id, salt.clone());
+        
+        // Same inputs should produce same hash
+        let commitment2 = StellarDuelsContract::calculate_commitment(&env, 1, move_id, salt.clone());
+        assert_eq!(commitment, commitment2);
+        
+        // Different move should produce different hash
+        let commitment3 = StellarDuelsContract::calculate_commitment(&env, 1, 2, salt.clone());
+        assert_ne!(commitment, commitment3);
+        
+        // Same move and salt in a different game should produce different hash
+        let commitment4 = StellarDuelsContract::calculate_commitment(&env, 2, move_id, salt);
+        assert_ne!(commitment, commitment4);
+    }
+    
+    #[test]
+    fn test_fee_taken_from_winner_and_credited_to_treasury() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &500); // 5%
+        
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Attack beats Defense: player one wins the 200 pot minus a 10 fee
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        
+        assert_eq!(token_client.balance(&p1), 1_090);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_treasury(&token), 10);
+        assert_eq!(token_client.balance(&client.address), 10);
+        
+        client.withdraw_treasury(&token, &admin, &10);
+        assert_eq!(token_client.balance(&admin), 10);
+        assert_eq!(client.get_treasury(&token), 0);
+    }
+    
+    #[test]
+    fn test_per_token_fee_rates() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &500);
+        
+        let xlm = create_token(&env);
+        let partner = create_token(&env);
+        client.set_fee_for_token(&partner, &0);
+        
+        assert_eq!(client.get_fee_for_token(&xlm), 500);
+        assert_eq!(client.get_fee_for_token(&partner), 0);
+        
+        let p1 = funded_player(&env, &client, &xlm, 1_000);
+        let p2 = funded_player(&env, &client, &xlm, 1_000);
+        StellarAssetClient::new(&env, &partner).mint(&p1, &1_000);
+        StellarAssetClient::new(&env, &partner).mint(&p2, &1_000);
+        
+        play_game(&client, &xlm, &p1, &p2, 100, 1, 2);
+        play_game(&client, &partner, &p1, &p2, 100, 1, 2);
+        
+        assert_eq!(client.get_treasury(&xlm), 10);
+        assert_eq!(client.get_treasury(&partner), 0);
+        assert_eq!(token::Client::new(&env, &partner).balance(&p1), 1_100);
+        
+        // Global changes still apply to tokens without an override
+        client.set_fee_bps(&200);
+        assert_eq!(client.get_fee_for_token(&xlm), 200);
+        assert_eq!(client.get_fee_for_token(&partner), 0);
+    }
+    
+    #[test]
+    fn test_draw_is_not_charged_a_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &1_000);
+        
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        play_game(&client, &token, &p1, &p2, 100, 3, 3);
+        
+        assert_eq!(token::Client::new(&env, &token).balance(&p1), 1_000);
+        assert_eq!(client.get_treasury(&token), 0);
+    }
+    
+    #[test]
+    fn test_fee_above_maximum_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        assert_eq!(
+            client.try_set_fee_for_token(&create_token(&env), &1_001),
+            Err(Ok(Error::InvalidArgument.into()))
+        );
+    }
+    
+    #[test]
+    fn test_invalid_round_counts_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        
+        for rounds in [0u32, 2, 4, 100, 101, u32::MAX] {
+            assert_eq!(
+                client.try_create_game(&p1, &GameConfig { rounds, ..GameConfig::new(100, &token) }),
+                Err(Ok(Error::InvalidRoundCount.into()))
+            );
+        }
+        
+        // Nothing was escrowed by the rejected attempts
+        assert_eq!(token::Client::new(&env, &token).balance(&p1), 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig { rounds: 99, ..GameConfig::new(100, &token) });
+        assert_eq!(client.get_game(&game_id).unwrap().rounds, 99);
+    }
+    
+    #[test]
+    fn test_validate_game_config_reports_each_problem() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        
+        let valid = GameConfig::new(100, &token);
+        assert_eq!(client.try_validate_game_config(&valid), Ok(Ok(())));
+        
+        for stake in [0i128, -100] {
+            assert_eq!(
+                client.try_validate_game_config(&GameConfig { stake_amount: stake, ..valid.clone() }),
+                Err(Ok(Error::InvalidStake))
+            );
+        }
+        assert_eq!(
+            client.try_validate_game_config(&GameConfig { rounds: 4, ..valid.clone() }),
+            Err(Ok(Error::InvalidRoundCount))
+        );
+        assert_eq!(
+            client.try_validate_game_config(&GameConfig { commit_window: MAX_COMMIT_WINDOW + 1, ..valid.clone() }),
+            Err(Ok(Error::InvalidArgument))
+        );
+        assert_eq!(
+            client.try_validate_game_config(&GameConfig { reveal_window: u32::MAX, ..valid.clone() }),
+            Err(Ok(Error::InvalidArgument))
+        );
+        
+        // create_game fails the same way
+        assert_eq!(
+            client.try_create_game(&p1, &GameConfig { stake_amount: 0, ..valid.clone() }),
+            Err(Ok(Error::InvalidStake.into()))
+        );
+        
+        client.set_max_active_games(&1);
+        client.create_game(&p1, &valid);
+        assert_eq!(client.try_validate_game_config(&valid), Err(Ok(Error::LobbyFull)));
+    }
+    
+    #[test]
+    fn test_best_of_three_match() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig { rounds: 3, ..GameConfig::new(100, &token) });
+        client.join_game(&game_id, &p2, &token);
+        
+        // Round 1: player one wins, no payout yet
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(game.state, GameState::WaitingForPlayer);
+        assert_eq!(game.current_round, 2);
+        assert_eq!((game.p1_round_wins, game.p2_round_wins), (1, 0));
+        assert_eq!(game.p1_move, 0);
+        assert_eq!(token_client.balance(&client.address), 200);
+        
+        // Round 2: a draw is replayed without counting
+        let game = play_round(&client, game_id, &token, &p1, &p2, 2, 2);
+        assert_eq!(game.current_round, 3);
+        assert_eq!((game.p1_round_wins, game.p2_round_wins), (1, 0));
+        
+        // Round 3: player two levels the score
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 3);
+        assert_eq!((game.p1_round_wins, game.p2_round_wins), (1, 1));
+        assert_eq!(game.state, GameState::WaitingForPlayer);
+        
+        // Round 4: player two clinches the match and the pot
+        let game = play_round(&client, game_id, &token, &p1, &p2, 2, 1);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.winner, Some(p2.clone()));
+        assert_eq!(token_client.balance(&p2), 1_100);
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(client.get_player(&p2).unwrap().wins, 1);
+        assert_eq!(client.get_player(&p1).unwrap().losses, 1);
+    }
+    
+    #[test]
+    fn test_deposit_and_withdraw_internal_balance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let player = funded_player(&env, &client, &token, 1_000);
+        
+        assert_eq!(client.deposit(&player, &400, &token), 400);
+        assert_eq!(client.get_balance(&player, &token), 400);
+        assert_eq!(token_client.balance(&player), 600);
+        
+        assert_eq!(client.withdraw(&player, &150, &token), 250);
+        assert_eq!(token_client.balance(&player), 750);
+        assert_eq!(token_client.balance(&client.address), 250);
+        
+        assert_eq!(
+            client.try_withdraw(&player, &251, &token),
+            Err(Ok(Error::InsufficientFunds.into()))
+        );
+    }
+    
+    #[test]
+    fn test_auto_rematch_chains_games_until_opt_out() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        client.deposit(&p1, &500, &token);
+        client.deposit(&p2, &500, &token);
+        client.set_auto_rematch(&p1, &p2, &true);
+        client.set_auto_rematch(&p2, &p1, &true);
+        
+        // Finishing the first game chains into a pre-joined rematch
+        let first_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        let rematch_id = client.get_game(&first_id).unwrap().next_game_id.unwrap();
+        let rematch = client.get_game(&rematch_id).unwrap();
+        assert_eq!(rematch.player_two, Some(p2.clone()));
+        assert_eq!(rematch.stake_amount, 100);
+        assert!(client.get_active_games().contains(rematch_id));
+        assert_eq!(client.get_balance(&p1, &token), 400);
+        assert_eq!(client.get_balance(&p2, &token), 400);
+        assert_eq!(client.get_auto_rematch(&p1, &p2), AUTO_REMATCH_LIMIT - 1);
+        
+        // The rematch settles into internal balances and chains again
+        play_round(&client, rematch_id, &token, &p1, &p2, 1, 3);
+        let third_id = client.get_game(&rematch_id).unwrap().next_game_id.unwrap();
+        assert_eq!(client.get_balance(&p2, &token), 400 + 200 - 100);
+        assert_eq!(client.get_balance(&p1, &token), 400 - 100);
+        
+        // Player two opts out: the chain stops after this game
+        client.set_auto_rematch(&p2, &p1, &false);
+        let last = play_round(&client, third_id, &token, &p1, &p2, 2, 2);
+        assert_eq!(last.next_game_id, None);
+        assert_eq!(client.get_balance(&p1, &token), 400);
+        assert_eq!(client.get_balance(&p2, &token), 600);
+    }
+    
+    #[test]
+    fn test_auto_rematch_requires_internal_balance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        client.deposit(&p1, &500, &token);
+        client.deposit(&p2, &50, &token);
+        client.set_auto_rematch(&p1, &p2, &true);
+        client.set_auto_rematch(&p2, &p1, &true);
+        
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        assert_eq!(client.get_game(&game_id).unwrap().next_game_id, None);
+        assert_eq!(client.get_auto_rematch(&p1, &p2), AUTO_REMATCH_LIMIT);
+    }
+    
+    #[test]
+    fn test_auto_rematch_skipped_when_settings_no_longer_valid() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        client.deposit(&p1, &500, &token);
+        client.deposit(&p2, &500, &token);
+        client.set_auto_rematch(&p1, &p2, &true);
+        client.set_auto_rematch(&p2, &p1, &true);
+        
+        // The admin raises the minimum stake while the game is in play
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        client.set_stake_bounds(&200, &1_000);
+        
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(game.next_game_id, None);
+        assert_eq!(client.get_auto_rematch(&p1, &p2), AUTO_REMATCH_LIMIT);
+        assert_eq!((client.get_balance(&p1, &token), client.get_balance(&p2, &token)), (500, 500));
+    }
+    
+    #[test]
+    fn test_reconcile_matches_then_detects_stray_funds() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // A finished game (fee in treasury), an open game, a joined game, a deposit
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        client.create_game(&p1, &GameConfig::new(50, &token));
+        let joined = client.create_game(&p1, &GameConfig::new(30, &token));
+        client.join_game(&joined, &p2, &token);
+        client.deposit(&p2, &70, &token);
+        
+        assert_eq!(client.reconcile(&token), (10 + 50 + 60 + 70, 190));
+        
+        // Tokens sent straight to the contract aren't accounted for anywhere
+        StellarAssetClient::new(&env, &token).mint(&client.address, &5);
+        assert_eq!(client.reconcile(&token), (190, 195));
+        
+        // Other tokens are reconciled independently
+        assert_eq!(client.reconcile(&create_token(&env)), (0, 0));
+    }
+    
+    #[test]
+    fn test_commitment_reused_across_games_fails_reveal() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let first = client.create_game(&p1, &GameConfig::new(100, &token));
+        let second = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&second, &p2, &token);
+        
+        // Player one mistakenly reuses the commitment computed for the first game
+        let salt = BytesN::from_array(&env, &[7u8; 32]);
+        let stale = StellarDuelsContract::calculate_commitment(&env, first, 1, salt.clone());
+        let p2_salt = BytesN::from_array(&env, &[8u8; 32]);
+        client.commit_move(&second, &p1, &stale);
+        client.commit_move(
+            &second,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, second, 2, p2_salt),
+        );
+        
+        assert_eq!(
+            client.try_reveal_move(&second, &p1, &1, &salt),
+            Err(Ok(Error::CommitmentMismatch.into()))
+        );
+    }
+    
+    #[test]
+    fn test_notification_prefs_round_trip() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let player = Address::generate(&env);
+        assert_eq!(client.register_player(&player).notification_prefs, 0);
+        
+        let prefs = NOTIFY_REVEAL_TURN | NOTIFY_GAME_FINALIZED;
+        client.set_notification_prefs(&player, &prefs);
+        assert_eq!(client.get_player(&player).unwrap().notification_prefs, prefs);
+        
+        // Re-registering keeps the stored preferences
+        assert_eq!(client.register_player(&player).notification_prefs, prefs);
+        
+        client.set_notification_prefs(&player, &0);
+        assert_eq!(client.get_player(&player).unwrap().notification_prefs, 0);
+    }
+    
+    #[test]
+    fn test_notification_prefs_reject_unknown_bits() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let player = Address::generate(&env);
+        client.register_player(&player);
+        assert_eq!(
+            client.try_set_notification_prefs(&player, &(NOTIFY_ALL + 1)).err(),
+            Some(Ok(Error::InvalidArgument.into()))
+        );
+    }
+    
+    #[test]
+    fn test_set_game_counter_controls_next_id() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        
+        client.set_game_counter(&1_000);
+        assert_eq!(client.create_game(&p1, &GameConfig::new(10, &token)), 1_000);
+        assert_eq!(client.create_game(&p1, &GameConfig::new(10, &token)), 1_001);
+    }
+    
+    #[test]
+    fn test_set_game_counter_cannot_go_backwards() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        
+        client.create_game(&p1, &GameConfig::new(10, &token));
+        client.create_game(&p1, &GameConfig::new(10, &token));
+        assert_eq!(client.try_set_game_counter(&2), Err(Ok(Error::InvalidArgument.into())));
+    }
+    
+    #[test]
+    fn test_distribute_exact_three_way_split() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        StellarAssetClient::new(&env, &token).mint(&client.address, &100);
+        
+        let a = Address::generate(&env);
+        let b = Address::generate(&env);
+        let c = Address::generate(&env);
+        let recipients = Vec::from_array(&env, [a.clone(), b.clone(), c.clone()]);
+        
+        let mut lowest = a.clone();
+        for addr in [&b, &c] {
+            if *addr < lowest {
+                lowest = addr.clone();
+            }
+        }
+        
+        env.as_contract(&client.address, || {
+            let game = StellarDuelsContract::new_game(&env, 1, a.clone(), 50, token.clone(), 1);
+            StellarDuelsContract::distribute_exact(&env, &game, &recipients, 100);
+        });
+        
+        // 100 / 3 = 33 each, the lowest address takes the extra stroop
+        let token_client = token::Client::new(&env, &token);
+        for addr in [&a, &b, &c] {
+            let expected = if *addr == lowest { 34 } else { 33 };
+            assert_eq!(token_client.balance(addr), expected);
+        }
+        assert_eq!(token_client.balance(&client.address), 0);
+    }
+    
+    #[test]
+    fn test_distribute_exact_into_internal_balances() {
+        let env = Env::default();
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        
+        let a = Address::generate(&env);
+        let b = Address::generate(&env);
+        let recipients = Vec::from_array(&env, [a.clone(), b.clone()]);
+        
+        env.as_contract(&client.address, || {
+            let mut game = StellarDuelsContract::new_game(&env, 1, a.clone(), 50, token.clone(), 1);
+            game.settle_internal = true;
+            StellarDuelsContract::distribute_exact(&env, &game, &recipients, 7);
+        });
+        
+        let total = client.get_balance(&a, &token) + client.get_balance(&b, &token);
+        assert_eq!(total, 7);
+        assert!(client.get_balance(&a, &token) >= 3 && client.get_balance(&b, &token) >= 3);
+    }
+    
+    #[test]
+    fn test_check_reveal_matches_stored_commitment() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        let salt = BytesN::from_array(&env, &[9u8; 32]);
+        let wrong_salt = BytesN::from_array(&env, &[10u8; 32]);
+        
+        // Nothing committed yet
+        assert!(!client.check_reveal(&game_id, &p1, &3, &salt));
+        
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 3, salt.clone()),
+        );
+        
+        // Works before the opponent commits and never changes the game
+        assert!(client.check_reveal(&game_id, &p1, &3, &salt));
+        assert!(!client.check_reveal(&game_id, &p1, &2, &salt));
+        assert!(!client.check_reveal(&game_id, &p1, &3, &wrong_salt));
+        assert!(!client.check_reveal(&game_id, &p2, &3, &salt));
+        assert!(!client.check_reveal(&game_id, &Address::generate(&env), &3, &salt));
+        assert_eq!(client.get_game(&game_id).unwrap().p1_move, 0);
+    }
+    
+    #[test]
+    fn test_hidden_progress_masks_opponent_commit() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let spectator = Address::generate(&env);
+        
+        let config = GameConfig {
+            hide_progress: true,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        
+        let salt = BytesN::from_array(&env, &[3u8; 32]);
+        let commitment = StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt);
+        client.commit_move(&game_id, &p1, &commitment);
+        
+        // Player one sees their own commitment; nobody else can tell it exists
+        assert!(client.get_game_progress(&game_id, &p1).p1_committed);
+        assert!(!client.get_game_progress(&game_id, &p2).p1_committed);
+        assert!(!client.get_game_progress(&game_id, &spectator).p1_committed);
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        assert_eq!(client.get_game(&game_id).unwrap().p1_commitment, zero);
+        
+        // Once both committed everything is visible again
+        let p2_salt = BytesN::from_array(&env, &[4u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt),
+        );
+        let progress = client.get_game_progress(&game_id, &spectator);
+        assert!(progress.p1_committed && progress.p2_committed);
+        assert_eq!(client.get_game(&game_id).unwrap().p1_commitment, commitment);
+    }
+    
+    #[test]
+    fn test_visible_progress_shows_opponent_commit() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        let salt = BytesN::from_array(&env, &[3u8; 32]);
+        let commitment = StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt);
+        client.commit_move(&game_id, &p1, &commitment);
+        
+        let progress = client.get_game_progress(&game_id, &p2);
+        assert_eq!(
+            progress,
+            GameProgress {
+                round: 1,
+                p1_committed: true,
+                p2_committed: false,
+                p1_revealed: false,
+                p2_revealed: false,
+            }
+        );
+        assert_eq!(client.get_game(&game_id).unwrap().p1_commitment, commitment);
+    }
+    
+    #[test]
+    fn test_export_replay_lists_every_round_in_order() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig { rounds: 3, ..GameConfig::new(100, &token) });
+        client.join_game(&game_id, &p2, &token);
+        
+        // Not finished yet
+        assert_eq!(
+            client.try_export_replay(&game_id),
+            Err(Ok(Error::GameNotCompleted.into()))
+        );
+        
+        let played = [(1u32, 2u32), (3, 3), (2, 1), (2, 3)];
+        for (m1, m2) in played {
+            play_round(&client, game_id, &token, &p1, &p2, m1, m2);
+        }
+        
+        let replay = client.export_replay(&game_id);
+        assert_eq!(replay.player_one, p1);
+        assert_eq!(replay.player_two, p2);
+        assert_eq!(replay.stake_amount, 100);
+        assert_eq!(replay.rounds, 3);
+        assert_eq!(replay.winner, Some(p1.clone()));
+        assert_eq!((replay.p1_round_wins, replay.p2_round_wins), (2, 1));
+        
+        assert_eq!(replay.history.len(), 4);
+        for (i, (m1, m2)) in played.iter().enumerate() {
+            let record = replay.history.get(i as u32).unwrap();
+            assert_eq!(record.round, i as u32 + 1);
+            assert_eq!((record.p1_move, record.p2_move), (*m1, *m2));
+        }
+        assert_eq!(replay.history.get(1).unwrap().winner, None);
+        assert_eq!(replay.history.get(2).unwrap().winner, Some(p2.clone()));
+    }
+    
+    /// Fill the leaderboard with LEADERBOARD_SIZE players holding 100, 99, ... wins
+    fn fill_leaderboard(env: &Env, client: &StellarDuelsContractClient) -> Vec<Address> {
+        let mut players = Vec::new(env);
+        env.as_contract(&client.address, || {
+            for i in 0..LEADERBOARD_SIZE {
+                let player = Address::generate(env);
+                StellarDuelsContract::leaderboard_insert(env, &player, 100 - i);
+                players.push_back(player);
+            }
+        });
+        players
+    }
+    
+    #[test]
+    fn test_leaderboard_insert_tie_with_last_place_is_rejected() {
+        let env = Env::default();
+        let client = setup_contract(&env);
+        let players = fill_leaderboard(&env, &client);
+        let last = players.get(LEADERBOARD_SIZE - 1).unwrap();
+        
+        let newcomer = Address::generate(&env);
+        env.as_contract(&client.address, || {
+            StellarDuelsContract::leaderboard_insert(&env, &newcomer, 100 - (LEADERBOARD_SIZE - 1));
+        });
+        
+        let board = client.get_leaderboard();
+        assert_eq!(board.len(), LEADERBOARD_SIZE);
+        assert_eq!(board.get(LEADERBOARD_SIZE - 1).unwrap().0, last);
+        assert!(!board.iter().any(|(addr, _)| addr == newcomer));
+    }
+    
+    #[test]
+    fn test_leaderboard_insert_below_cutoff_changes_nothing() {
+        let env = Env::default();
+        let client = setup_contract(&env);
+        fill_leaderboard(&env, &client);
+        let before = client.get_leaderboard();
+        
+        env.as_contract(&client.address, || {
+            StellarDuelsContract::leaderboard_insert(&env, &Address::generate(&env), 1);
+        });
+        
+        assert_eq!(client.get_leaderboard(), before);
+    }
+    
+    #[test]
+    fn test_leaderboard_insert_above_cutoff_evicts_last_place() {
+        let env = Env::default();
+        let client = setup_contract(&env);
+        let players = fill_leaderboard(&env, &client);
+        let last = players.get(LEADERBOARD_SIZE - 1).unwrap();
+        let second = players.get(1).unwrap();
+        
+        let newcomer = Address::generate(&env);
+        env.as_contract(&client.address, || {
+            StellarDuelsContract::leaderboard_insert(&env, &newcomer, 99);
+        });
+        
+        let board = client.get_leaderboard();
+        assert_eq!(board.len(), LEADERBOARD_SIZE);
+        assert!(!board.iter().any(|(addr, _)| addr == last));
+        // Ties keep the earlier holder ahead
+        assert_eq!(board.get(1).unwrap(), (second, 99));
+        assert_eq!(board.get(2).unwrap(), (newcomer, 99));
+        
+        // Still sorted descending
+        for i in 1..board.len() {
+            assert!(board.get(i - 1).unwrap().1 >= board.get(i).unwrap().1);
+        }
+    }
+    
+    #[test]
+    fn test_leaderboard_tracks_wins_from_games() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let a = funded_player(&env, &client, &token, 1_000);
+        let b = funded_player(&env, &client, &token, 1_000);
+        let c = funded_player(&env, &client, &token, 1_000);
+        
+        play_game(&client, &token, &a, &b, 10, 2, 1); // b wins
+        play_game(&client, &token, &c, &b, 10, 1, 2); // c wins
+        play_game(&client, &token, &c, &a, 10, 1, 2); // c wins
+        
+        let board = client.get_leaderboard();
+        assert_eq!(board.len(), 2);
+        assert_eq!(board.get(0).unwrap(), (c, 2));
+        assert_eq!(board.get(1).unwrap(), (b, 1));
+    }
+    
+    #[test]
+    fn test_move_count_configures_valid_reveals() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        assert_eq!(client.get_move_count(), 3);
+        
+        client.set_move_count(&5);
+        assert_eq!(client.get_move_count(), 5);
+        
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Spock (5) beats Defense (2)
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 5, 2);
+        assert_eq!(client.get_game(&game_id).unwrap().winner, Some(p1.clone()));
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        let salt = BytesN::from_array(&env, &[5u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 6, salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt.clone()),
+        );
+        assert_eq!(client.try_reveal_move(&game_id, &p1, &6, &salt), Err(Ok(Error::InvalidMove.into())));
+        assert!(!client.check_reveal(&game_id, &p1, &6, &salt));
+    }
+    
+    #[test]
+    fn test_classic_games_reject_extended_moves() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 4, 1);
+        let salt = BytesN::from_array(&env, &[1u8; 32]);
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &4, &salt),
+            Err(Ok(Error::InvalidMove.into()))
+        );
+    }
+    
+    #[test]
+    fn test_ruleset_chosen_per_game() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // An Extended game accepts Lizard even though the house plays Classic
+        let config = GameConfig { ruleset: Ruleset::Extended, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        assert_eq!(client.get_game(&game_id).unwrap().move_count, 5);
+        client.join_game(&game_id, &p2, &token);
+        let game = play_round(&client, game_id, &token, &p1, &p2, 4, 3);
+        assert_eq!(game.winner, Some(p1.clone()));
+        
+        // A Classic game stays on three moves even once the house is extended
+        client.set_move_count(&5);
+        let config = GameConfig { ruleset: Ruleset::Classic, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        assert_eq!(client.get_game(&game_id).unwrap().move_count, 3);
+        client.join_game(&game_id, &p2, &token);
+        let salt = BytesN::from_array(&env, &[1u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 5, salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt.clone()),
+        );
+        assert_eq!(client.try_reveal_move(&game_id, &p1, &5, &salt), Err(Ok(Error::InvalidMove.into())));
+    }
+    
+    #[test]
+    fn test_extended_beat_matrix() {
+        let env = Env::default();
+        let mut game = StellarDuelsContract::new_game(&env, 1, Address::generate(&env), 100, Address::generate(&env), 1);
+        game.player_two = Some(Address::generate(&env));
+        let p1 = Some(game.player_one.clone());
+        let p2 = game.player_two.clone();
+        
+        // Each move beats exactly two others
+        let beats = [
+            (Move::Attack, Move::Defense),
+            (Move::Attack, Move::Lizard),
+            (Move::Defense, Move::Magic),
+            (Move::Defense, Move::Lizard),
+            (Move::Magic, Move::Attack),
+            (Move::Magic, Move::Spock),
+            (Move::Lizard, Move::Magic),
+            (Move::Lizard, Move::Spock),
+            (Move::Spock, Move::Attack),
+            (Move::Spock, Move::Defense),
+        ];
+        for (winner, loser) in beats {
+            let (w, l) = (winner as u32, loser as u32);
+            assert_eq!(StellarDuelsContract::determine_winner(&game, w, l), p1, "{} vs {}", w, l);
+            assert_eq!(StellarDuelsContract::determine_winner(&game, l, w), p2, "{} vs {}", l, w);
+        }
+        
+        // Identical moves draw, and every other pairing is covered above
+        for m in 1..=5u32 {
+            assert_eq!(StellarDuelsContract::determine_winner(&game, m, m), None);
+        }
+        assert_eq!(beats.len() * 2 + 5, 25);
+    }
+    
+    #[test]
+    fn test_unsupported_move_count_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        assert_eq!(client.try_set_move_count(&4), Err(Ok(Error::InvalidArgument.into())));
+    }
+    
+    #[test]
+    fn test_completion_bonus_paid_until_pool_empty() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &0);
+        
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        StellarAssetClient::new(&env, &token).mint(&admin, &30);
+        assert_eq!(client.fund_rewards(&30, &token), 30);
+        client.set_reward_bps(&1_000); // 10% of stake per participant
+        
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Full bonus of 10 each
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        assert_eq!(token_client.balance(&p1), 1_110);
+        assert_eq!(token_client.balance(&p2), 910);
+        assert_eq!(client.get_rewards_pool(&token), 10);
+        
+        // Pool runs dry: player one gets the last 10, player two nothing
+        play_game(&client, &token, &p1, &p2, 100, 3, 3);
+        assert_eq!(token_client.balance(&p1), 1_120);
+        assert_eq!(token_client.balance(&p2), 910);
+        assert_eq!(client.get_rewards_pool(&token), 0);
+        
+        // No more bonuses once empty
+        play_game(&client, &token, &p1, &p2, 100, 3, 3);
+        assert_eq!(token_client.balance(&p1), 1_120);
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+    
+    #[test]
+    fn test_commit_both_requires_operator_and_players() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &0);
+        
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        let game = client.commit_both(
+            &game_id,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        
+        // Operator and both players all had to sign
+        let signers: std::vec::Vec<Address> = env.auths().into_iter().map(|(a, _)| a).collect();
+        assert!(signers.contains(&admin));
+        assert!(signers.contains(&p1));
+        assert!(signers.contains(&p2));
+        assert_eq!(game.state, GameState::MovesCommitted);
+        
+        // The players reveal and settle as usual
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        assert_eq!(client.finalize_game(&game_id, &token).winner, Some(p1));
+    }
+    
+    #[test]
+    fn test_commit_both_fails_without_player_auth() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &0);
+        
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        let c1 = BytesN::from_array(&env, &[1u8; 32]);
+        let c2 = BytesN::from_array(&env, &[2u8; 32]);
+        
+        // Only the operator signs
+        env.mock_auths(&[soroban_sdk::testutils::MockAuth {
+            address: &admin,
+            invoke: &soroban_sdk::testutils::MockAuthInvoke {
+                contract: &client.address,
+                fn_name: "commit_both",
+                args: (game_id, c1.clone(), c2.clone()).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_commit_both(&game_id, &c1, &c2).is_err());
+    }
+    
+    #[test]
+    fn test_reveal_before_both_commit_reports_why() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        let salt = BytesN::from_array(&env, &[6u8; 32]);
+        
+        // No opponent yet, nobody committed
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &1, &salt),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &1, &salt),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        
+        // Exactly one side committed: either player is told to wait
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt.clone()),
+        );
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &1, &salt),
+            Err(Ok(Error::OpponentNotCommitted.into()))
+        );
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p2, &1, &salt),
+            Err(Ok(Error::OpponentNotCommitted.into()))
+        );
+    }
+    
+    #[test]
+    fn test_reveal_after_completion_is_invalid_state() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &1, &BytesN::from_array(&env, &[1u8; 32])),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+    }
+    
+    #[test]
+    fn test_max_active_games_blocks_creation_until_games_complete() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        assert_eq!(client.get_max_active_games(), DEFAULT_MAX_ACTIVE_GAMES);
+        client.set_max_active_games(&2);
+        
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let first = client.create_game(&p1, &GameConfig::new(10, &token));
+        client.create_game(&p1, &GameConfig::new(10, &token));
+        assert_eq!(client.get_active_games_count(), 2);
+        assert_eq!(
+            client.try_create_game(&p1, &GameConfig::new(10, &token)),
+            Err(Ok(Error::LobbyFull.into()))
+        );
+        
+        // Completing a game frees a slot
+        client.join_game(&first, &p2, &token);
+        play_round(&client, first, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.get_active_games_count(), 1);
+        client.create_game(&p1, &GameConfig::new(10, &token));
+    }
+    
+    #[test]
+    fn test_game_outcome_for_each_resolution() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let open = client.create_game(&p1, &GameConfig::new(10, &token));
+        assert_eq!(client.get_game_outcome(&open), None);
+        
+        let won = play_game(&client, &token, &p1, &p2, 10, 1, 2);
+        assert_eq!(client.get_game_outcome(&won), Some(GameOutcome::Won));
+        
+        let drawn = play_game(&client, &token, &p1, &p2, 10, 2, 2);
+        assert_eq!(client.get_game_outcome(&drawn), Some(GameOutcome::Draw));
+        
+        // A best-of-3 mid-match has no outcome yet
+        let series = client.create_game(&p1, &GameConfig { rounds: 3, ..GameConfig::new(10, &token) });
+        client.join_game(&series, &p2, &token);
+        play_round(&client, series, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.get_game_outcome(&series), None);
+    }
+    
+    #[test]
+    fn test_stale_counter_never_overwrites_existing_games() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let first = client.create_game(&p1, &GameConfig::new(10, &token));
+        let second = client.create_game(&p2, &GameConfig::new(20, &token));
+        
+        // Simulate a lost counter entry
+        env.as_contract(&client.address, || {
+            env.storage().persistent().remove(&DataKey::GameCounter);
+        });
+        
+        let third = client.create_game(&p1, &GameConfig::new(30, &token));
+        assert_eq!(third, 3);
+        assert_eq!(client.get_game(&first).unwrap().stake_amount, 10);
+        assert_eq!(client.get_game(&second).unwrap().player_one, p2);
+        assert_eq!(client.create_game(&p1, &GameConfig::new(40, &token)), 4);
+    }
+    
+    #[test]
+    fn test_dispute_on_completed_game_is_record_only() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        assert_eq!(client.get_dispute(&game_id), None);
+        
+        let dispute = client.flag_dispute(&game_id, &p2, &7);
+        assert_eq!(dispute.flagged_by, p2);
+        assert_eq!(dispute.reason_code, 7);
+        assert_eq!(client.get_dispute(&game_id), Some(dispute));
+        
+        // Paid-out pots can't be moved again
+        assert_eq!(
+            client.try_resolve_dispute(&game_id, &DisputeResolution::AwardPlayerTwo, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        
+        let resolved = client.resolve_dispute(&game_id, &DisputeResolution::Dismissed, &token);
+        assert_eq!(resolved.resolution, DisputeResolution::Dismissed);
+        assert_eq!(client.get_game(&game_id).unwrap().winner, Some(p1));
+        
+        // Resolution is final
+        assert_eq!(
+            client.try_resolve_dispute(&game_id, &DisputeResolution::Upheld, &token),
+            Err(Ok(Error::DisputeResolved.into()))
+        );
+    }
+    
+    #[test]
+    fn test_dispute_can_settle_a_stuck_game() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Player two went silent after joining
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        client.flag_dispute(&game_id, &p1, &1);
+        
+        client.resolve_dispute(&game_id, &DisputeResolution::AwardPlayerOne, &token);
+        
+        let game = client.get_game(&game_id).unwrap();
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!(client.get_game_outcome(&game_id), Some(GameOutcome::Arbitrated));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert!(!client.get_active_games().contains(game_id));
+        
+        // The arbitrated game can't be played out for a second payout
+        assert_eq!(
+            client.try_commit_move(&game_id, &p1, &BytesN::from_array(&env, &[1u8; 32])),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!(token_client.balance(&client.address), 0);
+    }
+    
+    #[test]
+    fn test_only_participants_can_flag_disputes() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        
+        assert_eq!(
+            client.try_flag_dispute(&game_id, &Address::generate(&env), &1),
+            Err(Ok(Error::NotAuthorized.into()))
+        );
+    }
+    
+    /// Create and join a game, then seal both moves so betting opens
+    fn committed_game(
+        client: &StellarDuelsContractClient,
+        token: &Address,
+        p1: &Address,
+        p2: &Address,
+        p1_move: u32,
+        p2_move: u32,
+    ) -> u64 {
+        let env = &client.env;
+        let game_id = client.create_game(p1, &GameConfig::new(100, token));
+        client.join_game(&game_id, p2, token);
+        let p1_salt = BytesN::from_array(env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(env, &[2u8; 32]);
+        client.commit_move(
+            &game_id,
+            p1,
+            &StellarDuelsContract::calculate_commitment(env, game_id, p1_move, p1_salt),
+        );
+        client.commit_move(
+            &game_id,
+            p2,
+            &StellarDuelsContract::calculate_commitment(env, game_id, p2_move, p2_salt),
+        );
+        game_id
+    }
+    
+    /// Reveal the moves sealed by committed_game and finalize
+    fn reveal_and_finalize(
+        client: &StellarDuelsContractClient,
+        game_id: u64,
+        token: &Address,
+        p1: &Address,
+        p2: &Address,
+        p1_move: u32,
+        p2_move: u32,
+    ) -> Game {
+        let env = &client.env;
+        client.reveal_move(&game_id, p1, &p1_move, &BytesN::from_array(env, &[1u8; 32]));
+        client.reveal_move(&game_id, p2, &p2_move, &BytesN::from_array(env, &[2u8; 32]));
+        client.finalize_game(&game_id, token)
+    }
+    
+    #[test]
+    fn test_bet_totals_track_placed_and_cancelled_bets() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let alice = funded_player(&env, &client, &token, 1_000);
+        let bob = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.get_bet_totals(&game_id), (0, 0));
+        
+        assert_eq!(client.place_bet(&game_id, &alice, &p1, &50, &token), (50, 0));
+        assert_eq!(client.place_bet(&game_id, &bob, &p2, &30, &token), (50, 30));
+        assert_eq!(client.place_bet(&game_id, &alice, &p2, &20, &token), (50, 50));
+        
+        assert_eq!(client.cancel_bet(&game_id, &alice), 70);
+        assert_eq!(client.get_bet_totals(&game_id), (0, 30));
+        assert_eq!(client.get_bets(&game_id).len(), 1);
+        assert_eq!(token_client.balance(&alice), 1_000);
+        
+        assert_eq!(client.place_bet(&game_id, &alice, &p1, &10, &token), (10, 30));
+        assert_eq!(client.reconcile(&token), (200 + 40, 240));
+    }
+    
+    #[test]
+    fn test_bets_pay_winner_backers_pro_rata() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let alice = funded_player(&env, &client, &token, 1_000);
+        let bob = funded_player(&env, &client, &token, 1_000);
+        let carol = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        client.place_bet(&game_id, &alice, &p1, &20, &token);
+        client.place_bet(&game_id, &bob, &p1, &10, &token);
+        client.place_bet(&game_id, &carol, &p2, &70, &token);
+        
+        reveal_and_finalize(&client, game_id, &token, &p1, &p2, 1, 2);
+        client.settle_bets(&game_id, &token);
+        
+        // Pool of 100 split 2:1 between the player-one backers: 67 / 33 with dust to the first
+        assert_eq!(token_client.balance(&alice), 1_000 - 20 + 67);
+        assert_eq!(token_client.balance(&bob), 1_000 - 10 + 33);
+        assert_eq!(token_client.balance(&carol), 930);
+        assert_eq!(client.reconcile(&token), (0, 0));
+        assert_eq!(client.get_bet_totals(&game_id), (0, 0));
+        
+        // Can't be paid twice
+        assert_eq!(client.try_settle_bets(&game_id, &token), Err(Ok(Error::NoBets.into())));
+    }
+    
+    #[test]
+    fn test_bets_refunded_on_draw() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let alice = funded_player(&env, &client, &token, 1_000);
+        let bob = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 3, 3);
+        client.place_bet(&game_id, &alice, &p1, &25, &token);
+        client.place_bet(&game_id, &bob, &p2, &40, &token);
+        
+        // Not settleable before the game ends
+        assert_eq!(
+            client.try_settle_bets(&game_id, &token),
+            Err(Ok(Error::GameNotCompleted.into()))
+        );
+        
+        reveal_and_finalize(&client, game_id, &token, &p1, &p2, 3, 3);
+        client.settle_bets(&game_id, &token);
+        
+        assert_eq!(token_client.balance(&alice), 1_000);
+        assert_eq!(token_client.balance(&bob), 1_000);
+    }
+    
+    #[test]
+    fn test_betting_closes_once_a_move_is_revealed() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let alice = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        client.reveal_move(&game_id, &p1, &1, &BytesN::from_array(&env, &[1u8; 32]));
+        assert_eq!(
+            client.try_place_bet(&game_id, &alice, &p1, &10, &token),
+            Err(Ok(Error::BettingClosed.into()))
+        );
+    }
+    
+    /// Play a 5% fee game between two fresh players so the treasury gains 10
+    fn seed_treasury(client: &StellarDuelsContractClient, token: &Address) {
+        let env = &client.env;
+        let a = funded_player(env, client, token, 1_000);
+        let b = funded_player(env, client, token, 1_000);
+        play_game(client, token, &a, &b, 100, 1, 2);
+    }
+    
+    #[test]
+    fn test_early_reveal_bonus_decays_linearly() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        for _ in 0..3 {
+            seed_treasury(&client, &token);
+        }
+        assert_eq!(client.get_treasury(&token), 30);
+        client.set_reveal_bonus(&8);
+        
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        
+        // A 400-a-side pot pays a 40 fee, so neither bonus hits the fee cap
+        let config = GameConfig {
+            reveal_window: 100,
+            ..GameConfig::new(400, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        let start = env.ledger().sequence();
+        let game = client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        assert_eq!(game.reveal_deadline, start + 100);
+        
+        // Window start earns the full bonus, halfway through half of it;
+        // both are paid once the game settles
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        env.ledger().set_sequence_number(start + 50);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        assert_eq!(token_client.balance(&p1), 600);
+        client.finalize_game(&game_id, &token);
+        assert_eq!(token_client.balance(&p1), 600 + 760 + 8);
+        assert_eq!(token_client.balance(&p2), 600 + 4);
+        assert_eq!(client.get_treasury(&token), 30 + 40 - 12);
+        
+        // At the deadline the bonus has decayed to nothing
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        let game = client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        env.ledger().set_sequence_number(game.reveal_deadline);
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        client.finalize_game(&game_id, &token);
+        assert_eq!(client.get_treasury(&token), 58 + 40);
+    }
+    
+    #[test]
+    fn test_reveal_bonus_never_exceeds_the_game_fee() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        for _ in 0..3 {
+            seed_treasury(&client, &token);
+        }
+        client.set_reveal_bonus(&8);
+        
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let config = GameConfig {
+            reveal_window: 100,
+            ..GameConfig::new(1, &token)
+        };
+        
+        // Instant reveals in a min-stake draw: refunded, no fee, no bonus
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        play_round(&client, game_id, &token, &p1, &p2, 1, 1);
+        assert_eq!((token_client.balance(&p1), token_client.balance(&p2)), (1_000, 1_000));
+        assert_eq!(client.get_treasury(&token), 30);
+        
+        // A decided 100-a-side game pays a 10 fee, so each bonus is cut to 5
+        // and together the pair gets back exactly what it paid in
+        let game_id = client.create_game(&p1, &GameConfig { stake_amount: 100, ..config });
+        client.join_game(&game_id, &p2, &token);
+        play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(token_client.balance(&p1), 1_000 + 90 + 5);
+        assert_eq!(token_client.balance(&p2), 900 + 5);
+        assert_eq!(client.get_treasury(&token), 30);
+    }
+    
+    #[test]
+    fn test_no_reveal_bonus_by_default() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        seed_treasury(&client, &token);
+        
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        play_game(&client, &token, &p1, &p2, 100, 2, 2);
+        assert_eq!(client.get_treasury(&token), 10);
+        assert_eq!(token::Client::new(&env, &token).balance(&p1), 1_000);
+    }
+    
+    #[test]
+    fn test_admin_toggles_moderated_flag() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        
+        let first = BytesN::from_array(&env, &[7u8; 32]);
+        let second = BytesN::from_array(&env, &[8u8; 32]);
+        assert_eq!(client.post_comment(&game_id, &p1, &first), 1);
+        assert_eq!(client.post_comment(&game_id, &p2, &second), 2);
+        assert_eq!(client.get_comments(&game_id).get(1).unwrap(), (p2.clone(), second));
+        
+        assert!(!client.get_game(&game_id).unwrap().moderated);
+        client.set_moderated(&game_id, &true);
+        assert!(client.get_game(&game_id).unwrap().moderated);
+        client.set_moderated(&game_id, &false);
+        assert!(!client.get_game(&game_id).unwrap().moderated);
+        
+        // Moderation hides comments client-side, it never drops them
+        assert_eq!(client.get_comments(&game_id).len(), 2);
+    }
+    
+    #[test]
+    fn test_non_admin_cannot_moderate() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        
+        // Only the player authorizes, so the admin check fails
+        env.mock_auths(&[soroban_sdk::testutils::MockAuth {
+            address: &p1,
+            invoke: &soroban_sdk::testutils::MockAuthInvoke {
+                contract: &client.address,
+                fn_name: "set_moderated",
+                args: (game_id, true).into_val(&env),
+                sub_invokes: &[],
+            },
+        }]);
+        assert!(client.try_set_moderated(&game_id, &true).is_err());
+        assert!(!client.get_game(&game_id).unwrap().moderated);
+    }
+    
+    #[test]
+    fn test_expected_value_favors_higher_rating() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let player = Address::generate(&env);
+        client.register_player(&player);
+        assert_eq!(client.get_player(&player).unwrap().rating, 1200);
+        
+        // 200 points ahead: 7597 bps to win 2000 from a 1000 stake
+        assert_eq!(client.expected_value(&player, &1_000, &1000), 519);
+        // 200 points behind: the mirror image (rounded down)
+        assert_eq!(client.expected_value(&player, &1_000, &1400), -520);
+        // Even match: break-even
+        assert_eq!(client.expected_value(&player, &1_000, &1200), 0);
+        // Interpolated between table steps (10 of 25 points into 0..25)
+        assert_eq!(client.expected_value(&player, &10_000, &1190), 286);
+        // Huge gaps saturate rather than reaching certainty
+        assert_eq!(client.expected_value(&player, &1_000, &0), 980);
+        // Stakes too large to price are rejected instead of overflowing
+        assert_eq!(
+            client.try_expected_value(&player, &(i128::MAX / 2), &1200),
+            Err(Ok(Error::InvalidStake.into()))
+        );
+    }
+    
+    #[test]
+    fn test_withdrawal_timelock_holds_winnings() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        assert_eq!(
+            client.try_set_withdrawal_timelock(&(MAX_WITHDRAWAL_TIMELOCK + 1)),
+            Err(Ok(Error::InvalidArgument.into()))
+        );
+        client.set_withdrawal_timelock(&100);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        client.deposit(&p1, &500, &token);
+        client.deposit(&p2, &500, &token);
+        client.set_auto_rematch(&p1, &p2, &true);
+        client.set_auto_rematch(&p2, &p1, &true);
+        let first_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        let rematch_id = client.get_game(&first_id).unwrap().next_game_id.unwrap();
+        client.set_auto_rematch(&p1, &p2, &false);
+        
+        // Player two wins the internally settled rematch
+        let start = env.ledger().sequence();
+        play_round(&client, rematch_id, &token, &p1, &p2, 1, 3);
+        assert_eq!(client.get_balance(&p2, &token), 600);
+        assert_eq!(client.get_withdrawable(&p2, &token), 400);
+        
+        // Deposited funds can leave, fresh winnings cannot
+        assert_eq!(client.try_withdraw(&p2, &401, &token), Err(Ok(Error::DeadlineNotReached.into())));
+        assert_eq!(client.try_withdraw(&p2, &601, &token), Err(Ok(Error::InsufficientFunds.into())));
+        client.withdraw(&p2, &400, &token);
+        
+        env.ledger().set_sequence_number(start + 99);
+        assert_eq!(client.try_withdraw(&p2, &200, &token), Err(Ok(Error::DeadlineNotReached.into())));
+        
+        env.ledger().set_sequence_number(start + 100);
+        assert_eq!(client.get_withdrawable(&p2, &token), 200);
+        assert_eq!(client.withdraw(&p2, &200, &token), 0);
+    }
+    
+    #[test]
+    fn test_no_withdrawal_timelock_by_default() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        client.deposit(&p1, &500, &token);
+        client.deposit(&p2, &500, &token);
+        client.set_auto_rematch(&p1, &p2, &true);
+        client.set_auto_rematch(&p2, &p1, &true);
+        let first_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        let rematch_id = client.get_game(&first_id).unwrap().next_game_id.unwrap();
+        client.set_auto_rematch(&p1, &p2, &false);
+        
+        play_round(&client, rematch_id, &token, &p1, &p2, 1, 3);
+        assert_eq!(client.withdraw(&p2, &600, &token), 0);
+    }
+    
+    /// Play a drawn HashTiebreak game in a fresh environment with the given
+    /// salts; returns whether player one won
+    fn hash_tiebreak_draw(p1_salt: u8, p2_salt: u8) -> bool {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[p1_salt; 32]);
+        let p2_salt = BytesN::from_array(&env, &[p2_salt; 32]);
+        
+        let config = GameConfig {
+            draw_policy: DrawPolicy::HashTiebreak,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p1_salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        client.reveal_move(&game_id, &p1, &2, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        let game = client.finalize_game(&game_id, &token);
+        
+        // The whole pot goes to the tiebreak winner, who is credited a win
+        let winner = game.winner.clone().unwrap();
+        assert_eq!(token::Client::new(&env, &token).balance(&winner), 1_100);
+        assert_eq!(client.get_player(&winner).unwrap().wins, 1);
+        assert_eq!(game.outcome, GameOutcome::Won);
+        winner == p1
+    }
+    
+    #[test]
+    fn test_hash_tiebreak_is_deterministic() {
+        // Identical revealed values always produce the identical winner
+        for salt in 1..9u8 {
+            assert_eq!(hash_tiebreak_draw(salt, salt + 100), hash_tiebreak_draw(salt, salt + 100));
+        }
+        
+        // The salts, not seat order, decide: across a few salts both seats win
+        let p1_wins = (1..9u8).filter(|salt| hash_tiebreak_draw(*salt, salt + 100)).count();
+        assert!(p1_wins > 0 && p1_wins < 8);
+    }
+    
+    #[test]
+    fn test_refund_policy_still_refunds_draws() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 2, 2);
+        
+        let game = client.get_game(&game_id).unwrap();
+        assert_eq!(game.draw_policy, DrawPolicy::Refund);
+        assert_eq!(game.winner, None);
+        assert_eq!(token::Client::new(&env, &token).balance(&p1), 1_000);
+    }
+    
+    #[test]
+    fn test_donate_policy_moves_drawn_pot_to_rewards_pool() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig {
+            draw_policy: DrawPolicy::DonateToPool,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        let game = play_round(&client, game_id, &token, &p1, &p2, 2, 2);
+        
+        // The full 200 pot, untouched by the fee, now funds future bonuses
+        assert_eq!(game.winner, None);
+        assert_eq!(game.outcome, GameOutcome::Draw);
+        assert_eq!(client.get_rewards_pool(&token), 200);
+        assert_eq!(client.get_treasury(&token), 0);
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_player(&p1).unwrap().draws, 1);
+        assert_eq!(client.reconcile(&token), (200, 200));
+        
+        // A decisive game under the same policy pays out as usual
+        let config = GameConfig {
+            draw_policy: DrawPolicy::DonateToPool,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(token_client.balance(&p1), 990);
+        assert_eq!(client.get_rewards_pool(&token), 200);
+    }
+    
+    /// Seeded random operation sequences with invariant checks after every step
+    /// Each operation is attempted through the try_ client so invalid ones are
+    /// expected to fail cleanly; whatever happens, the invariants must hold.
+    mod state_machine {
+        use super::*;
+        use std::vec::Vec as StdVec;
+        
+        const PLAYERS: usize = 4;
+        const STARTING_BALANCE: i128 = 10_000;
+        const STEPS: u32 = 60;
+        
+        /// Small deterministic xorshift generator so seeds replay exactly
+        struct Rng(u64);
+        
+        impl Rng {
+            /// Spread small seeds over all bits; xorshift mixes them poorly
+            fn new(seed: u64) -> Self {
+                Rng(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15) | 1)
+            }
+            
+            fn next(&mut self) -> u64 {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                self.0
+            }
+            
+            fn below(&mut self, n: u64) -> u64 {
+                self.next() % n
+            }
+        }
+        
+        #[derive(Clone, Copy, Debug)]
+        enum Op {
+            Create,
+            Join,
+            Commit,
+            Reveal,
+            Finalize,
+            Cancel,
+        }
+        
+        // Weighted so games actually progress instead of piling up in the lobby
+        const OPS: [Op; 15] = [
+            Op::Create,
+            Op::Join, Op::Join,
+            Op::Commit, Op::Commit, Op::Commit, Op::Commit,
+            Op::Reveal, Op::Reveal, Op::Reveal, Op::Reveal,
+            Op::Finalize, Op::Finalize, Op::Finalize,
+            Op::Cancel,
+        ];
+        
+        /// A commitment made during the run, kept so it can be revealed later
+        struct Secret {
+            game_id: u64,
+            round: u32,
+            player: usize,
+            move_id: u32,
+            salt: [u8; 32],
+        }
+        
+        struct Harness {
+            env: Env,
+            client: StellarDuelsContractClient<'static>,
+            token: Address,
+            players: StdVec<Address>,
+            games: StdVec<u64>,
+            secrets: StdVec<Secret>,
+            minted: i128,
+        }
+        
+        impl Harness {
+            fn new() -> Self {
+                let env = Env::default();
+                env.mock_all_auths();
+                // Hundreds of calls share one test budget; only behaviour matters here
+                env.budget().reset_unlimited();
+                
+                let client = setup_contract(&env);
+                client.initialize(&Address::generate(&env), &250);
+                let token = create_token(&env);
+                let players = (0..PLAYERS)
+                    .map(|_| funded_player(&env, &client, &token, STARTING_BALANCE))
+                    .collect();
+                
+                Harness {
+                    env,
+                    client,
+                    token,
+                    players,
+                    games: StdVec::new(),
+                    secrets: StdVec::new(),
+                    minted: STARTING_BALANCE * PLAYERS as i128,
+                }
+            }
+            
+            fn game(&self, game_id: u64) -> Game {
+                self.client.get_game(&game_id).unwrap()
+            }
+            
+            /// Round a move would be played in; unknown games use round 0
+            fn round(&self, game_id: u64) -> u32 {
+                self.client.get_game(&game_id).map_or(0, |game| game.current_round)
+            }
+            
+            fn secret(&self, game_id: u64, round: u32, player: usize) -> Option<&Secret> {
+                self.secrets
+                    .iter()
+                    .find(|s| s.game_id == game_id && s.round == round && s.player == player)
+            }
+            
+            fn commit(&mut self, game_id: u64, player: usize, move_id: u32, salt: [u8; 32]) -> bool {
+                let round = self.round(game_id);
+                let commitment = StellarDuelsContract::calculate_commitment(
+                    &self.env,
+                    game_id,
+                    move_id,
+                    BytesN::from_array(&self.env, &salt),
+                );
+                let committed = self
+                    .client
+                    .try_commit_move(&game_id, &self.players[player], &commitment)
+                    .is_ok();
+                if committed {
+                    self.secrets.push(Secret { game_id, round, player, move_id, salt });
+                }
+                committed
+            }
+            
+            fn reveal(&self, game_id: u64, player: usize) -> bool {
+                let round = self.round(game_id);
+                let (move_id, salt) = match self.secret(game_id, round, player) {
+                    Some(secret) => (secret.move_id, secret.salt),
+                    None => (1, [0u8; 32]),
+                };
+                self.client
+                    .try_reveal_move(
+                        &game_id,
+                        &self.players[player],
+                        &move_id,
+                        &BytesN::from_array(&self.env, &salt),
+                    )
+                    .is_ok()
+            }
+            
+            fn step(&mut self, rng: &mut Rng) {
+                // An empty lobby can only move forward by creating a game
+                let active = self.client.get_active_games();
+                let op = if active.is_empty() {
+                    Op::Create
+                } else {
+                    OPS[rng.below(OPS.len() as u64) as usize]
+                };
+                
+                // Mostly push the oldest unfinished game along, sometimes another
+                // unfinished one, occasionally any ID at all
+                let game_id = match (active.is_empty(), rng.below(4)) {
+                    (false, 0 | 1) => active.get_unchecked(0),
+                    (false, 2) => active.get_unchecked(rng.below(active.len() as u64) as u32),
+                    _ => 1 + rng.below(self.games.len() as u64 + 1),
+                };
+                
+                // Mostly pick a player who can act: a joiner other than the
+                // creator, or one of the seated players for everything else
+                let mut player = rng.below(PLAYERS as u64) as usize;
+                if let (Some(game), true) = (self.client.get_game(&game_id), rng.below(4) != 0) {
+                    let creator = self.players.iter().position(|p| *p == game.player_one).unwrap();
+                    let seat = match (op, rng.below(2), game.player_two) {
+                        (Op::Join, _, _) => (creator + 1 + player % (PLAYERS - 1)) % PLAYERS,
+                        (_, 1, Some(p2)) => self.players.iter().position(|p| *p == p2).unwrap(),
+                        _ => creator,
+                    };
+                    player = seat;
+                }
+                
+                match op {
+                    Op::Create => {
+                        let stake = 1 + rng.below(100) as i128;
+                        let rounds = if rng.below(3) == 0 { 3 } else { 1 };
+                        if let Ok(Ok(id)) = self.client.try_create_game(
+                            &self.players[player],
+                            &GameConfig { rounds, ..GameConfig::new(stake, &self.token) },
+                        ) {
+                            self.games.push(id);
+                        }
+                    }
+                    Op::Join => {
+                        let _ = self.client.try_join_game(&game_id, &self.players[player], &self.token);
+                    }
+                    Op::Commit => {
+                        let move_id = 1 + rng.below(3) as u32;
+                        let mut salt = [0u8; 32];
+                        salt[..8].copy_from_slice(&rng.next().to_be_bytes());
+                        self.commit(game_id, player, move_id, salt);
+                    }
+                    Op::Reveal => {
+                        self.reveal(game_id, player);
+                    }
+                    Op::Finalize => {
+                        let _ = self.client.try_finalize_game(&game_id, &self.token);
+                    }
+                    Op::Cancel => {
+                        let _ = self.client.try_cancel_game(&game_id, &self.token);
+                    }
+                }
+            }
+            
+            fn assert_invariants(&self) {
+                // Solvency: the contract holds exactly what it owes
+                let (expected, actual) = self.client.reconcile(&self.token);
+                assert_eq!(expected, actual, "contract books out of balance");
+                
+                // No funds created or destroyed
+                let token_client = token::Client::new(&self.env, &self.token);
+                let held: i128 = self.players.iter().map(|p| token_client.balance(p)).sum();
+                assert_eq!(held + actual, self.minted, "token supply not conserved");
+                
+                // Only unfinished games are active, and every unfinished game is
+                let active = self.client.get_active_games();
+                for game_id in self.games.iter() {
+                    let game = self.game(*game_id);
+                    assert_eq!(game.state != GameState::Completed, active.contains(*game_id));
+                    
+                    // A match ends exactly when someone reaches the majority
+                    let wins_needed = game.rounds / 2 + 1;
+                    let decided = game.p1_round_wins.max(game.p2_round_wins) >= wins_needed;
+                    let ended_early = game.outcome == GameOutcome::Cancelled;
+                    assert_eq!(
+                        game.state == GameState::Completed,
+                        decided || game.rounds == 1 && game.outcome == GameOutcome::Draw || ended_early
+                    );
+                    if game.state == GameState::MovesCommitted {
+                        assert!(game.player_two.is_some());
+                    }
+                }
+            }
+            
+            /// Play every unfinished game to the end so any stuck funds show up
+            fn drain(&mut self, rng: &mut Rng) {
+                for game_id in self.games.clone() {
+                    if self.game(game_id).state == GameState::Completed {
+                        continue;
+                    }
+                    let p1 = self.players.iter().position(|p| *p == self.game(game_id).player_one).unwrap();
+                    if self.game(game_id).player_two.is_none() {
+                        let joiner = (p1 + 1) % PLAYERS;
+                        self.client.join_game(&game_id, &self.players[joiner], &self.token);
+                    }
+                    let p2 = self
+                        .players
+                        .iter()
+                        .position(|p| Some(p.clone()) == self.game(game_id).player_two)
+                        .unwrap();
+                    
+                    while self.game(game_id).state != GameState::Completed {
+                        let game = self.game(game_id);
+                        if game.state == GameState::WaitingForPlayer {
+                            let zero = BytesN::from_array(&self.env, &[0u8; 32]);
+                            for (seat, commitment) in [(p1, &game.p1_commitment), (p2, &game.p2_commitment)] {
+                                if *commitment == zero {
+                                    // Distinct moves so drained rounds always decide
+                                    let move_id = if seat == p1 { 1 } else { 2 };
+                                    let mut salt = [0u8; 32];
+                                    salt[..8].copy_from_slice(&rng.next().to_be_bytes());
+                                    assert!(self.commit(game_id, seat, move_id, salt));
+                                }
+                            }
+                        }
+                        let game = self.game(game_id);
+                        if game.p1_move == 0 {
+                            assert!(self.reveal(game_id, p1));
+                        }
+                        if game.p2_move == 0 {
+                            assert!(self.reveal(game_id, p2));
+                        }
+                        self.client.finalize_game(&game_id, &self.token);
+                        self.assert_invariants();
+                    }
+                }
+                
+                // Only house funds remain once everything has settled
+                assert!(self.client.get_active_games().is_empty());
+                let (_, actual) = self.client.reconcile(&self.token);
+                assert_eq!(actual, self.client.get_treasury(&self.token));
+            }
+        }
+        
+        fn run(seed: u64) {
+            let mut rng = Rng::new(seed);
+            let mut harness = Harness::new();
+            for _ in 0..STEPS {
+                harness.step(&mut rng);
+                harness.assert_invariants();
+            }
+            
+            // The random phase must get somewhere, not just fail every call
+            let finished = harness
+                .games
+                .iter()
+                .filter(|id| harness.game(**id).state == GameState::Completed)
+                .count();
+            assert!(finished > 0, "seed {} never finished a game", seed);
+            harness.drain(&mut rng);
+        }
+        
+        // One test per seed so they run in parallel and a failure names its seed
+        #[test]
+        fn test_state_machine_seed_2() {
+            run(2);
+        }
+        
+        #[test]
+        fn test_state_machine_seed_7() {
+            run(7);
+        }
+        
+        #[test]
+        fn test_state_machine_seed_11() {
+            run(11);
+        }
+        
+        #[test]
+        fn test_state_machine_seed_13() {
+            run(13);
+        }
+    }
+    
+    #[test]
+    fn test_frequent_opponent_is_most_played() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let player = funded_player(&env, &client, &token, 10_000);
+        let rival = funded_player(&env, &client, &token, 10_000);
+        let casual = funded_player(&env, &client, &token, 10_000);
+        let stranger = funded_player(&env, &client, &token, 10_000);
+        assert_eq!(client.get_frequent_opponent(&player), None);
+        
+        // One game each against the others, three against the rival,
+        // from either seat
+        play_game(&client, &token, &player, &casual, 10, 1, 2);
+        play_game(&client, &token, &rival, &player, 10, 1, 2);
+        play_game(&client, &token, &stranger, &player, 10, 1, 2);
+        play_game(&client, &token, &player, &rival, 10, 2, 2);
+        play_game(&client, &token, &player, &rival, 10, 3, 2);
+        
+        // An open game with no opponent doesn't count
+        client.create_game(&player, &GameConfig::new(10, &token));
+        
+        assert_eq!(client.get_frequent_opponent(&player), Some(rival.clone()));
+        assert_eq!(client.get_frequent_opponent(&casual), Some(player));
+    }
+    
+    #[test]
+    fn test_can_afford_matches_join_outcome() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let creator = funded_player(&env, &client, &token, 1_000);
+        let joiner = funded_player(&env, &client, &token, 250);
+        
+        // Stake above the joiner's balance: the pre-check and the join agree
+        let pricey = client.create_game(&creator, &GameConfig::new(251, &token));
+        assert!(!client.can_afford(&joiner, &251, &token));
+        assert_eq!(client.try_join_game(&pricey, &joiner, &token), Err(Ok(Error::BetsFull.into())));
+        
+        // Exactly the joiner's balance is affordable
+        let exact = client.create_game(&creator, &GameConfig::new(250, &token));
+        assert!(client.can_afford(&joiner, &250, &token));
+        assert!(client.try_join_game(&exact, &joiner, &token).is_ok());
+        assert!(!client.can_afford(&joiner, &1, &token));
+    }
+    
+    #[test]
+    fn test_start_season_archives_and_resets_leaderboard() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let a = funded_player(&env, &client, &token, 1_000);
+        let b = funded_player(&env, &client, &token, 1_000);
+        assert_eq!(client.get_current_season(), 0);
+        
+        play_game(&client, &token, &a, &b, 10, 1, 2); // a wins
+        play_game(&client, &token, &a, &b, 10, 1, 2); // a wins
+        play_game(&client, &token, &a, &b, 10, 2, 1); // b wins
+        
+        assert_eq!(client.start_season(), 1);
+        assert_eq!(client.get_current_season(), 1);
+        let archived = client.get_season_leaderboard(&0);
+        assert_eq!(archived.get(0).unwrap(), (a.clone(), 2));
+        assert_eq!(archived.get(1).unwrap(), (b.clone(), 1));
+        assert!(client.get_leaderboard().is_empty());
+        
+        // The new season ranks from zero; lifetime wins carry on
+        play_game(&client, &token, &a, &b, 10, 2, 1); // b wins
+        let board = client.get_leaderboard();
+        assert_eq!(board.len(), 1);
+        assert_eq!(board.get(0).unwrap(), (b.clone(), 1));
+        assert_eq!(client.get_player(&a).unwrap().wins, 2);
+        
+        assert_eq!(client.get_player(&b).unwrap().wins, 2);
+        assert_eq!(client.get_player_season(&b, &1).wins, 1);
+        
+        // The archive is frozen once the season is over
+        assert_eq!(client.get_season_leaderboard(&0), archived);
+        assert!(client.get_season_leaderboard(&1).is_empty());
+    }
+    
+    #[test]
+    fn test_enforced_reveal_order_rejects_player_two_first() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        
+        let config = GameConfig {
+            reveal_order_enforced: true,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p2, &2, &p2_salt),
+            Err(Ok(Error::RevealOutOfOrder.into()))
+        );
+        assert_eq!(client.get_game(&game_id).unwrap().p2_move, 0);
+        
+        // In order, both reveals go through
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        assert_eq!(client.finalize_game(&game_id, &token).winner, Some(p1));
+    }
+    
+    #[test]
+    fn test_get_contract_address_matches_registered_id() {
+        let env = Env::default();
+        let client = setup_contract(&env);
+        assert_eq!(client.get_contract_address(), client.address);
+    }
+    
+    /// Overwrite a player's rating directly in storage
+    fn set_rating(client: &StellarDuelsContractClient, player: &Address, rating: u32) {
+        let env = &client.env;
+        env.as_contract(&client.address, || {
+            let key = DataKey::Player(player.clone());
+            let mut profile: Player = env.storage().persistent().get(&key).unwrap();
+            profile.rating = rating;
+            env.storage().persistent().set(&key, &profile);
+        });
+    }
+    
+    #[test]
+    fn test_quick_match_joins_closest_rating() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let other_token = create_token(&env);
+        let caller = funded_player(&env, &client, &token, 1_000);
+        set_rating(&client, &caller, 1500);
+        
+        // Open games from creators rated 1000, 1450 (wrong stake), 1800 and
+        // 1400, plus a 1500 creator playing in another token
+        let mut open = std::vec::Vec::new();
+        for (rating, stake, game_token) in [
+            (1000, 100, &token),
+            (1450, 50, &token),
+            (1800, 100, &token),
+            (1400, 100, &token),
+            (1500, 100, &other_token),
+        ] {
+            let creator = funded_player(&env, &client, &token, 1_000);
+            StellarAssetClient::new(&env, &other_token).mint(&creator, &1_000);
+            set_rating(&client, &creator, rating);
+            open.push(client.create_game(&creator, &GameConfig::new(stake, game_token)));
+        }
+        
+        assert_eq!(client.quick_match(&caller, &100, &token), Some(open[3]));
+        assert_eq!(client.get_game(&open[3]).unwrap().player_two, Some(caller.clone()));
+        
+        // Next best is the 1800 creator (300 away) over the 1000 one (500 away)
+        let second = funded_player(&env, &client, &token, 1_000);
+        set_rating(&client, &second, 1500);
+        assert_eq!(client.quick_match(&second, &100, &token), Some(open[2]));
+        
+        // Nothing at an unmatched stake
+        assert_eq!(client.quick_match(&second, &75, &token), None);
+    }
+    
+    #[test]
+    fn test_seed_players_registers_and_credits() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &0);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        StellarAssetClient::new(&env, &token).mint(&admin, &1_000);
+        
+        // One player is already registered; seeding leaves their stats alone
+        let veteran = funded_player(&env, &client, &token, 0);
+        let a = Address::generate(&env);
+        let b = Address::generate(&env);
+        let entries = Vec::from_array(&env, [(a.clone(), 100), (b.clone(), 250), (veteran.clone(), 50)]);
+        
+        assert_eq!(client.seed_players(&entries, &token), 400);
+        assert_eq!(token_client.balance(&admin), 600);
+        assert_eq!(client.get_balance(&a, &token), 100);
+        assert_eq!(client.get_balance(&b, &token), 250);
+        assert_eq!(client.get_balance(&veteran, &token), 50);
+        assert!(client.get_player(&a).is_some());
+        assert!(client.get_player(&b).is_some());
+        
+        let (expected, actual) = client.reconcile(&token);
+        assert_eq!((expected, actual), (400, 400));
+        
+        // Seeded play-money is immediately usable
+        client.withdraw(&b, &250, &token);
+        assert_eq!(token_client.balance(&b), 250);
+        
+        // Entries whose sum overflows are rejected before anything moves
+        let entries = Vec::from_array(&env, [(a.clone(), i128::MAX), (b.clone(), 1)]);
+        assert_eq!(client.try_seed_players(&entries, &token), Err(Ok(Error::InvalidAmount.into())));
+        assert_eq!(token_client.balance(&admin), 600);
+    }
+    
+    #[test]
+    fn test_scout_report_gated_on_purchase() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig {
+            rounds: 3,
+            hide_progress: true,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        
+        // No purchase, no report
+        assert_eq!(
+            client.try_get_scout_report(&game_id, &p1),
+            Err(Ok(Error::ScoutNotPurchased.into()))
+        );
+        
+        // Scout costs 10% of the stake and reports the opponent's commit status
+        assert_eq!(client.buy_scout(&game_id, &p1, &token), 10);
+        assert_eq!(token::Client::new(&env, &token).balance(&p1), 890);
+        assert_eq!(client.get_treasury(&token), 10);
+        assert!(!client.get_scout_report(&game_id, &p1));
+        
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 3, p2_salt),
+        );
+        assert!(client.get_scout_report(&game_id, &p1));
+        
+        // The purchase is personal: the committed opponent learns nothing
+        assert_eq!(client.try_get_scout_report(&game_id, &p2), Err(Ok(Error::ScoutNotPurchased.into())));
+        assert_eq!(client.try_buy_scout(&game_id, &p2, &token), Err(Ok(Error::AlreadyCommitted.into())));
+        
+        // And it only covers the round it was bought in
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &3, &BytesN::from_array(&env, &[2u8; 32]));
+        client.finalize_game(&game_id, &token);
+        assert_eq!(
+            client.try_get_scout_report(&game_id, &p1),
+            Err(Ok(Error::ScoutNotPurchased.into()))
+        );
+    }
+    
+    #[test]
+    fn test_scout_requires_hidden_progress() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(client.try_buy_scout(&game_id, &p1, &token), Err(Ok(Error::ScoutUnavailable.into())));
+    }
+    
+    #[test]
+    fn test_get_stuck_games_reports_only_actionable() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 10_000);
+        let p2 = funded_player(&env, &client, &token, 10_000);
+        let salt = |byte: u8| BytesN::from_array(&env, &[byte; 32]);
+        let commit_both = |game_id: u64| {
+            client.commit_move(&game_id, &p1, &StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt(1)));
+            client.commit_move(&game_id, &p2, &StellarDuelsContract::calculate_commitment(&env, game_id, 2, salt(2)));
+        };
+        let new_game = |window: u32| {
+            let config = GameConfig {
+                reveal_window: window,
+                ..GameConfig::new(10, &token)
+            };
+            let game_id = client.create_game(&p1, &config);
+            client.join_game(&game_id, &p2, &token);
+            game_id
+        };
+        
+        // Healthy: still open, and committed inside its reveal window
+        client.create_game(&p1, &GameConfig::new(10, &token));
+        let in_window = new_game(1_000);
+        commit_both(in_window);
+        
+        // Both revealed, never finalized
+        let unfinalized = new_game(0);
+        commit_both(unfinalized);
+        client.reveal_move(&unfinalized, &p1, &1, &salt(1));
+        client.reveal_move(&unfinalized, &p2, &2, &salt(2));
+        
+        // Reveal window ran out with only one reveal
+        let overdue = new_game(10);
+        commit_both(overdue);
+        client.reveal_move(&overdue, &p1, &1, &salt(1));
+        
+        // Flagged for arbitration
+        let disputed = new_game(0);
+        client.flag_dispute(&disputed, &p2, &1);
+        
+        env.ledger().set_sequence_number(env.ledger().sequence() + 11);
+        let stuck = client.get_stuck_games(&10);
+        assert_eq!(
+            stuck,
+            Vec::from_array(
+                &env,
+                [
+                    (unfinalized, StuckReason::ReadyToFinalize),
+                    (overdue, StuckReason::RevealOverdue),
+                    (disputed, StuckReason::Disputed),
+                ]
+            )
+        );
+        
+        // The result is capped at `max`
+        assert_eq!(client.get_stuck_games(&1).len(), 1);
+    }
+    
+    /// Open a best-of-`rounds` round_stakes game with each player approving
+    /// the contract to pull the given allowances
+    fn round_stakes_game(
+        client: &StellarDuelsContractClient,
+        token: &Address,
+        p1: &Address,
+        p2: &Address,
+        rounds: u32,
+        allowances: (i128, i128),
+    ) -> u64 {
+        let token_client = token::Client::new(&client.env, token);
+        let expiry = client.env.ledger().sequence() + 1_000;
+        token_client.approve(p1, &client.address, &allowances.0, &expiry);
+        token_client.approve(p2, &client.address, &allowances.1, &expiry);
+        
+        let config = GameConfig {
+            rounds,
+            round_stakes: true,
+            ..GameConfig::new(100, token)
+        };
+        let game_id = client.create_game(p1, &config);
+        client.join_game(&game_id, p2, token);
+        game_id
+    }
+    
+    #[test]
+    fn test_round_stakes_forfeit_when_allowance_runs_out() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Only the first round is pulled up front
+        let game_id = round_stakes_game(&client, &token, &p1, &p2, 3, (300, 100));
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_game(&game_id).unwrap().pot, 200);
+        assert_eq!(client.reconcile(&token), (200, 200));
+        
+        // Player two takes round one, then can't fund rounds two and three
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 3);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!((game.p1_round_wins, game.p2_round_wins), (2, 1));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(token_client.balance(&p2), 900);
+        
+        // Forfeited rounds are on record with no moves
+        let history = client.get_round_history(&game_id);
+        assert_eq!(history.len(), 3);
+        let forfeit = history.get(2).unwrap();
+        assert_eq!((forfeit.round, forfeit.p1_move, forfeit.p2_move), (3, 0, 0));
+        assert_eq!(forfeit.winner, Some(p1));
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+    
+    #[test]
+    fn test_round_stakes_ends_on_score_when_nobody_can_pay() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = round_stakes_game(&client, &token, &p1, &p2, 5, (100, 100));
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(token_client.balance(&p2), 900);
+    }
+    
+    #[test]
+    fn test_round_stakes_join_needs_first_round_allowance() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let token_client = token::Client::new(&env, &token);
+        let expiry = env.ledger().sequence() + 1_000;
+        token_client.approve(&p1, &client.address, &300, &expiry);
+        token_client.approve(&p2, &client.address, &99, &expiry);
+        let config = GameConfig {
+            rounds: 3,
+            round_stakes: true,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        assert_eq!(
+            client.try_join_game(&game_id, &p2, &token),
+            Err(Ok(Error::InsufficientFunds.into()))
+        );
+        
+        // The creator pulling their allowance blocks the join just the same,
+        // instead of seating a match with nothing escrowed
+        token_client.approve(&p1, &client.address, &0, &expiry);
+        token_client.approve(&p2, &client.address, &300, &expiry);
+        assert_eq!(
+            client.try_join_game(&game_id, &p2, &token),
+            Err(Ok(Error::InsufficientFunds.into()))
+        );
+        assert_eq!(client.get_game(&game_id).unwrap().player_two, None);
+    }
+    
+    #[test]
+    fn test_finalize_pays_out_exactly_with_all_features() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let admin = Address::generate(&env);
+        client.initialize(&admin, &500);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        client.set_fee_for_token(&token, &300);
+        
+        // Treasury of 12 backs the reveal bonus; the rewards pool backs completion bonuses
+        seed_treasury(&client, &token);
+        seed_treasury(&client, &token);
+        assert_eq!(client.get_treasury(&token), 12);
+        client.set_reveal_bonus(&8);
+        StellarAssetClient::new(&env, &token).mint(&admin, &30);
+        client.fund_rewards(&30, &token);
+        client.set_reward_bps(&1_000);
+        
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let alice = funded_player(&env, &client, &token, 1_000);
+        let carol = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        
+        let config = GameConfig {
+            hide_progress: true,
+            reveal_window: 100,
+            draw_policy: DrawPolicy::HashTiebreak,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        let start = env.ledger().sequence();
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        client.place_bet(&game_id, &alice, &p1, &20, &token);
+        client.place_bet(&game_id, &carol, &p2, &30, &token);
+        
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        env.ledger().set_sequence_number(start + 50);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        client.finalize_game(&game_id, &token);
+        client.settle_bets(&game_id, &token);
+        
+        // Pot 200: fee 6, winner 194; reveal bonuses of 8 and 4 are each cut to
+        // half the fee (3); completion bonuses 10 each
+        assert_eq!(token_client.balance(&p1), 900 + 3 + 194 + 10);
+        assert_eq!(token_client.balance(&p2), 900 + 3 + 10);
+        assert_eq!(token_client.balance(&alice), 1_030);
+        assert_eq!(token_client.balance(&carol), 970);
+        assert_eq!(client.get_treasury(&token), 12);
+        assert_eq!(client.get_rewards_pool(&token), 10);
+        
+        // Nothing left behind for the game: the contract holds exactly the house funds
+        assert_eq!(client.reconcile(&token), (22, 22));
+    }
+    
+    #[test]
+    fn test_phase_timings_track_full_game() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        
+        env.ledger().set_sequence_number(100);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        let timings = client.get_phase_timings(&game_id);
+        assert_eq!(timings.created, 100);
+        assert_eq!((timings.joined, timings.committed, timings.completed), (0, 0, 0));
+        
+        env.ledger().set_sequence_number(110);
+        client.join_game(&game_id, &p2, &token);
+        env.ledger().set_sequence_number(115);
+        let game = client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        assert_eq!((game.timings.p1_committed, game.timings.p2_committed), (115, 0));
+        env.ledger().set_sequence_number(125);
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        let timings = client.get_game(&game_id).unwrap().timings;
+        assert_eq!((timings.p1_committed, timings.p2_committed), (115, 125));
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        env.ledger().set_sequence_number(160);
+        client.finalize_game(&game_id, &token);
+        
+        assert_eq!(
+            client.get_phase_timings(&game_id),
+            PhaseTimings {
+                created: 100,
+                joined: 110,
+                p1_committed: 115,
+                p2_committed: 125,
+                committed: 125,
+                p1_revealed: 125,
+                p2_revealed: 125,
+                completed: 160,
+            }
+        );
+    }
+    
+    #[test]
+    fn test_salt_reuse_across_games_rejected_when_enabled() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Off by default: the same salts work game after game
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        
+        client.set_salt_history(&1);
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &1, &BytesN::from_array(&env, &[1u8; 32])),
+            Err(Ok(Error::SaltReused.into()))
+        );
+        
+        // Histories are per player: others may pick the same salts
+        let p3 = funded_player(&env, &client, &token, 1_000);
+        let p4 = funded_player(&env, &client, &token, 1_000);
+        play_game(&client, &token, &p3, &p4, 100, 1, 2);
+        
+        // Only the last salt is remembered, so a fresh one frees the old one
+        let game_id = client.create_game(&p3, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p4, &token);
+        let p3_salt = BytesN::from_array(&env, &[7u8; 32]);
+        let p4_salt = BytesN::from_array(&env, &[8u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p3,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p3_salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p4,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p4_salt.clone()),
+        );
+        client.reveal_move(&game_id, &p3, &1, &p3_salt);
+        client.reveal_move(&game_id, &p4, &2, &p4_salt);
+        client.finalize_game(&game_id, &token);
+        play_game(&client, &token, &p3, &p4, 100, 1, 2);
+    }
+    
+    #[test]
+    fn test_sponsored_game_leaves_stake_and_winnings_to_player() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let sponsor = Address::generate(&env);
+        let newcomer = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&newcomer, &1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // The newcomer is registered on the way in and stakes their own funds
+        let config = GameConfig::new(100, &token);
+        let game_id = client.create_sponsored_game(&sponsor, &newcomer, &config);
+        assert!(client.get_player(&newcomer).is_some());
+        let game = client.get_game(&game_id).unwrap();
+        assert_eq!(game.player_one, newcomer);
+        assert_eq!(game.sponsor, Some(sponsor.clone()));
+        assert_eq!(token_client.balance(&newcomer), 900);
+        
+        // The sponsor has no say in the game and receives nothing
+        client.join_game(&game_id, &p2, &token);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        client.commit_move(
+            &game_id,
+            &newcomer,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, BytesN::from_array(&env, &[2u8; 32])),
+        );
+        assert_eq!(
+            client.try_reveal_move(&game_id, &sponsor, &1, &p1_salt),
+            Err(Ok(Error::NotAuthorized.into()))
+        );
+        client.reveal_move(&game_id, &newcomer, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &BytesN::from_array(&env, &[2u8; 32]));
+        assert_eq!(client.finalize_game(&game_id, &token).winner, Some(newcomer.clone()));
+        assert_eq!(token_client.balance(&newcomer), 1_100);
+        assert_eq!(token_client.balance(&sponsor), 0);
+        
+        // Only a first game can be sponsored
+        assert_eq!(
+            client.try_create_sponsored_game(&sponsor, &newcomer, &config),
+            Err(Ok(Error::AlreadyPlayed.into()))
+        );
+    }
+    
+    #[test]
+    fn test_match_status_follows_best_of_five() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig { rounds: 5, ..GameConfig::new(100, &token) });
+        let status = |current_round, p1_round_wins, p2_round_wins| MatchStatus {
+            current_round,
+            rounds: 5,
+            p1_round_wins,
+            p2_round_wins,
+            wins_needed: 3,
+        };
+        assert_eq!(client.get_match_status(&game_id), status(1, 0, 0));
+        
+        client.join_game(&game_id, &p2, &token);
+        play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.get_match_status(&game_id), status(2, 1, 0));
+        
+        // A drawn round moves the round number on without scoring
+        play_round(&client, game_id, &token, &p1, &p2, 3, 3);
+        assert_eq!(client.get_match_status(&game_id), status(3, 1, 0));
+        
+        play_round(&client, game_id, &token, &p1, &p2, 2, 1);
+        play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.get_match_status(&game_id), status(5, 2, 1));
+        
+        // Clinching leaves the final score in place
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(client.get_match_status(&game_id), status(5, 3, 1));
+    }
+    
+    #[test]
+    fn test_match_open_games_merges_two_lobbies() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_a = client.create_game(&p1, &GameConfig::new(100, &token));
+        let game_b = client.create_game(&p2, &GameConfig::new(100, &token));
+        let game = client.match_open_games(&game_a, &game_b);
+        assert_eq!(game.player_two, Some(p2.clone()));
+        
+        // Both authorized; the second game is closed and holds nothing
+        assert_eq!(env.auths().len(), 2);
+        let closed = client.get_game(&game_b).unwrap();
+        assert_eq!(closed.state, GameState::Completed);
+        assert_eq!(closed.outcome, GameOutcome::Merged);
+        assert_eq!(client.get_active_games(), Vec::from_array(&env, [game_a]));
+        assert_eq!(client.reconcile(&token), (200, 200));
+        
+        // The merged game plays out like any other
+        play_round(&client, game_a, &token, &p1, &p2, 1, 2);
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+    
+    #[test]
+    fn test_match_open_games_rejects_mismatched_stakes() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_a = client.create_game(&p1, &GameConfig::new(100, &token));
+        let game_b = client.create_game(&p2, &GameConfig::new(50, &token));
+        assert_eq!(
+            client.try_match_open_games(&game_a, &game_b),
+            Err(Ok(Error::IncompatibleOptions.into()))
+        );
+        
+        // A creator can't be paired with themselves either
+        let game_c = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(client.try_match_open_games(&game_a, &game_c), Err(Ok(Error::SelfPlay.into())));
+        assert_eq!(client.get_active_games_count(), 3);
+    }
+    
+    #[test]
+    fn test_move_performance_tallies_decided_rounds() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        play_game(&client, &token, &p1, &p2, 10, 3, 1); // Magic beats Attack
+        play_game(&client, &token, &p1, &p2, 10, 1, 2); // Attack beats Defense
+        play_game(&client, &token, &p1, &p2, 10, 3, 2); // Defense beats Magic
+        play_game(&client, &token, &p1, &p2, 10, 3, 3); // Draw: not counted
+        
+        assert_eq!(
+            client.get_move_performance(&p1),
+            Vec::from_array(&env, [(1, 1, 0), (3, 1, 1)])
+        );
+        assert_eq!(
+            client.get_move_performance(&p2),
+            Vec::from_array(&env, [(1, 0, 1), (2, 1, 1)])
+        );
+        assert!(client.get_move_performance(&Address::generate(&env)).is_empty());
+    }
+    
+    #[test]
+    fn test_bets_capped_per_game_with_repeat_bets_merged() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        client.set_max_bets_per_game(&2);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let alice = funded_player(&env, &client, &token, 1_000);
+        let bob = funded_player(&env, &client, &token, 1_000);
+        let carol = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        client.place_bet(&game_id, &alice, &p1, &20, &token);
+        client.place_bet(&game_id, &bob, &p2, &10, &token);
+        
+        // Topping up an existing entry still works at the cap
+        client.place_bet(&game_id, &alice, &p1, &5, &token);
+        let bets = client.get_bets(&game_id);
+        assert_eq!(bets.len(), 2);
+        assert_eq!(bets.get_unchecked(0).amount, 25);
+        
+        // A new entry does not, and nothing is taken from the bettor
+        assert_eq!(
+            client.try_place_bet(&game_id, &carol, &p1, &10, &token),
+            Err(Ok(Error::BetsFull.into()))
+        );
+        assert_eq!(
+            client.try_place_bet(&game_id, &alice, &p2, &10, &token),
+            Err(Ok(Error::BetsFull.into()))
+        );
+        assert_eq!(token::Client::new(&env, &token).balance(&carol), 1_000);
+        assert_eq!(client.get_bet_totals(&game_id), (25, 10));
+        
+        reveal_and_finalize(&client, game_id, &token, &p1, &p2, 1, 2);
+        client.settle_bets(&game_id, &token);
+        assert_eq!(token::Client::new(&env, &token).balance(&alice), 1_010);
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+    
+    /// Open a game with a commit window and seat both players
+    fn commit_window_game(
+        client: &StellarDuelsContractClient,
+        token: &Address,
+        p1: &Address,
+        p2: &Address,
+        window: u32,
+    ) -> u64 {
+        let game_id = client.create_game(p1, &GameConfig { commit_window: window, ..GameConfig::new(100, token) });
+        client.join_game(&game_id, p2, token);
+        game_id
+    }
+    
+    #[test]
+    fn test_commit_timeout_awards_pot_to_the_committed_player() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        env.ledger().set_sequence_number(100);
+        let game_id = commit_window_game(&client, &token, &p1, &p2, 50);
+        assert_eq!(client.get_game(&game_id).unwrap().commit_deadline, 150);
+        let salt = BytesN::from_array(&env, &[1u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt),
+        );
+        
+        // Player one still has time
+        env.ledger().set_sequence_number(150);
+        assert_eq!(
+            client.try_claim_commit_timeout(&game_id, &token),
+            Err(Ok(Error::DeadlineNotReached.into()))
+        );
+        assert_eq!(client.get_stuck_games(&10).len(), 0);
+        
+        env.ledger().set_sequence_number(151);
+        assert_eq!(
+            client.get_stuck_games(&10),
+            Vec::from_array(&env, [(game_id, StuckReason::CommitOverdue)])
+        );
+        let game = client.claim_commit_timeout(&game_id, &token);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.outcome, GameOutcome::TimedOut);
+        assert_eq!(game.winner, Some(p2.clone()));
+        assert_eq!(token_client.balance(&p2), 1_100);
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(client.get_player(&p2).unwrap().wins, 1);
+        assert_eq!(client.get_player(&p1).unwrap().losses, 1);
+        assert!(client.get_active_games().is_empty());
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+    
+    #[test]
+    fn test_commit_timeout_refunds_when_nobody_committed() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = commit_window_game(&client, &token, &p1, &p2, 10);
+        env.ledger().set_sequence_number(env.ledger().sequence() + 11);
+        let game = client.claim_commit_timeout(&game_id, &token);
+        assert_eq!(game.winner, None);
+        assert_eq!(token_client.balance(&p1), 1_000);
+        assert_eq!(token_client.balance(&p2), 1_000);
+        assert_eq!(client.get_player(&p1).unwrap().losses, 0);
+        
+        // The settled game can't be played again for a second payout
+        assert_eq!(
+            client.try_commit_move(&game_id, &p1, &BytesN::from_array(&env, &[1u8; 32])),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        
+        // Games without a commit window can't be claimed
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        assert_eq!(
+            client.try_claim_commit_timeout(&game_id, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        let game_id = commit_window_game(&client, &token, &p1, &p2, 0);
+        env.ledger().set_sequence_number(env.ledger().sequence() + 1_000);
+        assert_eq!(
+            client.try_claim_commit_timeout(&game_id, &token),
+            Err(Ok(Error::TimeoutNotAvailable.into()))
+        );
+    }
+    
+    #[test]
+    fn test_fairness_proof_recomputes_commitments_and_winner() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig { rounds: 3, ..GameConfig::new(100, &token) });
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(
+            client.try_export_fairness_proof(&game_id),
+            Err(Ok(Error::GameNotCompleted.into()))
+        );
+        for (m1, m2) in [(1u32, 2u32), (3, 3), (1, 2)] {
+            play_round(&client, game_id, &token, &p1, &p2, m1, m2);
+        }
+        
+        let proof = client.export_fairness_proof(&game_id);
+        assert_eq!(proof.rounds.len(), 3);
+        assert_eq!(proof.winner, Some(p1.clone()));
+        let game = client.get_game(&game_id).unwrap();
+        for round in proof.rounds.iter() {
+            // The revealed salts hash back to exactly what was committed
+            assert_eq!(round.p1_recomputed, round.p1_commitment);
+            assert_eq!(round.p2_recomputed, round.p2_commitment);
+            assert_eq!(round.p1_salt, BytesN::from_array(&env, &[1u8; 32]));
+            assert_eq!(
+                round.winner,
+                StellarDuelsContract::determine_winner(&game, round.p1_move, round.p2_move)
+            );
+        }
+        assert_eq!(proof.rounds.get(1).unwrap().winner, None);
+    }
+    
+    #[test]
+    fn test_reveal_timeout_awards_pot_to_the_revealer() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig { reveal_window: 20, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 3, p1_salt.clone()),
+        );
+        let game = client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, BytesN::from_array(&env, &[2u8; 32])),
+        );
+        client.reveal_move(&game_id, &p1, &3, &p1_salt);
+        
+        // The silent player can't be timed out early
+        env.ledger().set_sequence_number(game.reveal_deadline);
+        assert_eq!(
+            client.try_claim_reveal_timeout(&game_id, &token),
+            Err(Ok(Error::DeadlineNotReached.into()))
+        );
+        
+        env.ledger().set_sequence_number(game.reveal_deadline + 1);
+        let game = client.claim_reveal_timeout(&game_id, &token);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.outcome, GameOutcome::TimedOut);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_player(&p1).unwrap().wins, 1);
+        assert_eq!(client.get_player(&p2).unwrap().losses, 1);
+        assert!(client.get_active_games().is_empty());
+        assert_eq!(client.reconcile(&token), (0, 0));
+    }
+    
+    #[test]
+    fn test_reveal_timeout_refunds_when_nobody_revealed() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Without a reveal window there is nothing to claim
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        env.ledger().set_sequence_number(env.ledger().sequence() + 1_000);
+        assert_eq!(
+            client.try_claim_reveal_timeout(&game_id, &token),
+            Err(Ok(Error::TimeoutNotAvailable.into()))
+        );
+        
+        let config = GameConfig { reveal_window: 20, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        for (player, salt) in [(&p1, 1u8), (&p2, 2u8)] {
+            client.commit_move(
+                &game_id,
+                player,
+                &StellarDuelsContract::calculate_commitment(&env, game_id, 1, BytesN::from_array(&env, &[salt; 32])),
+            );
+        }
+        env.ledger().set_sequence_number(env.ledger().sequence() + 21);
+        let game = client.claim_reveal_timeout(&game_id, &token);
+        assert_eq!(game.winner, None);
+        assert_eq!(game.state, GameState::Completed);
+        
+        // Refunded; only the stakes of the first, still open game remain held
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_active_games_count(), 1);
+    }
+    
+    #[test]
+    fn test_cancel_open_game_returns_stake() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(token_client.balance(&p1), 900);
+        
+        let game = client.cancel_game(&game_id, &token);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.outcome, GameOutcome::Cancelled);
+        assert_eq!(game.winner, None);
+        assert_eq!(token_client.balance(&p1), 1_000);
+        assert!(!client.get_active_games().contains(game_id));
+        assert_eq!(client.reconcile(&token), (0, 0));
+        
+        // Cancelled games can't be joined, and joined games can't be cancelled
+        assert_eq!(client.try_join_game(&game_id, &p2, &token), Err(Ok(Error::InvalidGameState.into())));
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(client.try_cancel_game(&game_id, &token), Err(Ok(Error::AlreadyJoined.into())));
+    }
+    
+    #[test]
+    fn test_cancel_after_join_panics() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(client.try_cancel_game(&game_id, &token), Err(Ok(Error::AlreadyJoined.into())));
+    }
+    
+    /// Whether the contract's last invocation published ("duel", `action`) with `data`
+    fn published<D: IntoVal<Env, soroban_sdk::Val>>(
+        client: &StellarDuelsContractClient,
+        action: Symbol,
+        data: D,
+    ) -> bool {
+        let env = &client.env;
+        let topics: Vec<soroban_sdk::Val> = (EVENT_TOPIC, action).into_val(env);
+        env.events().all().contains((client.address.clone(), topics, data.into_val(env)))
+    }
+    
+    #[test]
+    fn test_events_published_for_each_transition() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert!(published(&client, symbol_short!("created"), (game_id, p1.clone(), 100i128)));
+        
+        client.join_game(&game_id, &p2, &token);
+        assert!(published(&client, symbol_short!("joined"), (game_id, p2.clone())));
+        
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()),
+        );
+        assert!(published(&client, symbol_short!("committed"), (game_id, p1.clone())));
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        assert!(published(&client, symbol_short!("committed"), (game_id, p2.clone())));
+        
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        assert!(published(&client, symbol_short!("revealed"), (game_id, p1.clone(), 1u32)));
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        assert!(published(&client, symbol_short!("revealed"), (game_id, p2.clone(), 2u32)));
+        
+        client.finalize_game(&game_id, &token);
+        assert!(published(&client, symbol_short!("finalized"), (game_id, Some(p1.clone()), 200i128)));
+        
+        // Draws carry no winner
+        let game_id = play_game(&client, &token, &p1, &p2, 100, 3, 3);
+        assert!(published(&client, symbol_short!("finalized"), (game_id, None::<Address>, 200i128)));
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(50, &token));
+        client.cancel_game(&game_id, &token);
+        assert!(published(&client, symbol_short!("cancelled"), (game_id, 50i128)));
+    }
+    
+    #[test]
+    fn test_elo_rewards_upsets_and_pulls_draws_together() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let underdog = funded_player(&env, &client, &token, 1_000);
+        let favorite = funded_player(&env, &client, &token, 1_000);
+        assert_eq!(client.get_leaderboard_rating(&underdog), DEFAULT_RATING);
+        set_rating(&client, &favorite, 1400);
+        
+        // 200 points down: expected score ~0.24, so the win is worth ~24
+        play_game(&client, &token, &underdog, &favorite, 10, 1, 2);
+        let gain = client.get_leaderboard_rating(&underdog) - DEFAULT_RATING;
+        assert!(gain > 16, "gain was {}", gain);
+        assert_eq!(client.get_leaderboard_rating(&favorite), 1400 - gain);
+        
+        // A draw moves the lower rating up and the higher one down
+        let (low, high) = (client.get_leaderboard_rating(&underdog), client.get_leaderboard_rating(&favorite));
+        play_game(&client, &token, &underdog, &favorite, 10, 3, 3);
+        assert!(client.get_leaderboard_rating(&underdog) > low);
+        assert!(client.get_leaderboard_rating(&favorite) < high);
+        
+        // Equal ratings trade half the K-factor
+        let a = funded_player(&env, &client, &token, 1_000);
+        let b = funded_player(&env, &client, &token, 1_000);
+        play_game(&client, &token, &a, &b, 10, 1, 2);
+        assert_eq!(client.get_leaderboard_rating(&a), DEFAULT_RATING + 16);
+        assert_eq!(client.get_leaderboard_rating(&b), DEFAULT_RATING - 16);
+    }
+    
+    #[test]
+    fn test_get_active_games_paged() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        for _ in 0..5 {
+            client.create_game(&p1, &GameConfig::new(10, &token));
+        }
+        let all = client.get_active_games();
+        assert_eq!(client.get_active_games_count(), 5);
+        
+        assert_eq!(client.get_active_games_paged(&0, &2), all.slice(0..2));
+        assert_eq!(client.get_active_games_paged(&2, &2), all.slice(2..4));
+        // The last page is short
+        assert_eq!(client.get_active_games_paged(&4, &2), all.slice(4..5));
+        assert_eq!(client.get_active_games_paged(&0, &u32::MAX), all);
+        // Past the end or a zero limit yields nothing
+        assert_eq!(client.get_active_games_paged(&5, &2).len(), 0);
+        assert_eq!(client.get_active_games_paged(&0, &0).len(), 0);
+    }
+    
+    #[test]
+    fn test_get_player_games() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        assert_eq!(client.get_player_games(&p1).len(), 0);
+        
+        let first = client.create_game(&p1, &GameConfig::new(10, &token));
+        let second = client.create_game(&p1, &GameConfig::new(10, &token));
+        client.join_game(&second, &p2, &token);
+        
+        assert_eq!(client.get_player_games(&p1), Vec::from_array(&env, [first, second]));
+        assert_eq!(client.get_player_games(&p2), Vec::from_array(&env, [second]));
+    }
+    
+    #[test]
+    fn test_private_game_only_admits_invited_player() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let friend = funded_player(&env, &client, &token, 1_000);
+        let stranger = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig { invited: Some(friend.clone()), ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        assert_eq!(client.get_game(&game_id).unwrap().invited, Some(friend.clone()));
+        
+        // Strangers can neither join nor merge their own open game into it
+        assert_eq!(client.try_join_game(&game_id, &stranger, &token), Err(Ok(Error::InviteOnly.into())));
+        let stranger_game = client.create_game(&stranger, &GameConfig::new(100, &token));
+        assert_eq!(
+            client.try_match_open_games(&game_id, &stranger_game),
+            Err(Ok(Error::InviteOnly.into()))
+        );
+        
+        let game = client.join_game(&game_id, &friend, &token);
+        assert_eq!(game.player_two, Some(friend));
+    }
+    
+    #[test]
+    fn test_stake_bounds() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 10_000);
+        assert_eq!(client.get_stake_bounds(), (1, i128::MAX));
+        
+        client.set_stake_bounds(&50, &500);
+        assert_eq!(client.get_stake_bounds(), (50, 500));
+        for stake in [49i128, 501] {
+            assert_eq!(
+                client.try_create_game(&p1, &GameConfig::new(stake, &token)),
+                Err(Ok(Error::StakeOutOfBounds.into()))
+            );
+        }
+        // Non-positive stakes are still reported as invalid, not out of bounds
+        for stake in [0i128, -1] {
+            assert_eq!(
+                client.try_create_game(&p1, &GameConfig::new(stake, &token)),
+                Err(Ok(Error::InvalidStake.into()))
+            );
+        }
+        // Both bounds are inclusive
+        client.create_game(&p1, &GameConfig::new(50, &token));
+        client.create_game(&p1, &GameConfig::new(500, &token));
+        
+        assert_eq!(client.try_set_stake_bounds(&0, &500), Err(Ok(Error::InvalidArgument.into())));
+        assert_eq!(client.try_set_stake_bounds(&500, &50), Err(Ok(Error::InvalidArgument.into())));
+    }
+    
+    #[test]
+    fn test_huge_stakes_rejected_before_pot_overflows() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        
+        for stake in [i128::MAX, i128::MAX / 2 + 1, MAX_POT / 2 + 1] {
+            assert_eq!(
+                client.try_create_game(&p1, &GameConfig::new(stake, &token)),
+                Err(Ok(Error::InvalidStake.into()))
+            );
+        }
+        assert_eq!(client.try_validate_game_config(&GameConfig::new(MAX_POT / 2, &token)), Ok(Ok(())));
+        
+        // Round-stake pots grow with every round played
+        let config = GameConfig { rounds: 3, round_stakes: true, ..GameConfig::new(MAX_POT / 4, &token) };
+        assert_eq!(client.try_validate_game_config(&config), Err(Ok(Error::InvalidStake)));
+        let config = GameConfig { stake_amount: MAX_POT / 6, ..config };
+        assert_eq!(client.try_validate_game_config(&config), Ok(Ok(())));
+    }
+    
+    #[test]
+    fn test_rematch_reserves_seat_for_old_opponent() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let stranger = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig { ruleset: Ruleset::Extended, reveal_window: 50, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        assert_eq!(client.try_rematch(&game_id, &p1, &token), Err(Ok(Error::GameNotCompleted.into())));
+        client.join_game(&game_id, &p2, &token);
+        play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        
+        assert_eq!(
+            client.try_rematch(&game_id, &stranger, &token),
+            Err(Ok(Error::NotAuthorized.into()))
+        );
+        
+        // The winner offers a rematch and pays their stake up front
+        let before = token_client.balance(&p1);
+        let rematch_id = client.rematch(&game_id, &p1, &token);
+        assert_eq!(token_client.balance(&p1), before - 100);
+        assert_eq!(client.get_game(&game_id).unwrap().next_game_id, Some(rematch_id));
+        assert_eq!(client.try_rematch(&game_id, &p2, &token), Err(Ok(Error::InvalidGameState.into())));
+        
+        let rematch = client.get_game(&rematch_id).unwrap();
+        assert_eq!(rematch.state, GameState::WaitingForPlayer);
+        assert_eq!(rematch.player_one, p1);
+        assert_eq!(rematch.invited, Some(p2.clone()));
+        assert_eq!(rematch.stake_amount, 100);
+        assert_eq!(rematch.move_count, EXTENDED_MOVE_COUNT);
+        assert_eq!(rematch.reveal_window, 50);
+        assert_eq!(rematch.p1_move, 0);
+        
+        assert_eq!(
+            client.try_join_game(&rematch_id, &stranger, &token),
+            Err(Ok(Error::InviteOnly.into()))
+        );
+        let rematch = client.join_game(&rematch_id, &p2, &token);
+        assert_eq!(rematch.player_two, Some(p2));
+    }
+    
+    #[test]
+    fn test_join_with_different_token_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let worthless = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &worthless, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(
+            client.try_join_game(&game_id, &p2, &worthless),
+            Err(Ok(Error::TokenMismatch.into()))
+        );
+    }
+    
+    #[test]
+    fn test_finalize_pays_only_in_the_game_token() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let other = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.commit_move(&game_id, &p1, &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt.clone()));
+        client.commit_move(&game_id, &p2, &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()));
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        
+        assert_eq!(client.try_finalize_game(&game_id, &other), Err(Ok(Error::TokenMismatch.into())));
+        assert_eq!(client.finalize_game(&game_id, &token).state, GameState::Completed);
+    }
+    
+    #[test]
+    fn test_pause_blocks_new_games_but_not_settlement() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let open_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        let live_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        
+        client.set_paused(&true);
+        assert!(client.is_paused());
+        assert_eq!(
+            client.try_create_game(&p1, &GameConfig::new(100, &token)),
+            Err(Ok(Error::Paused.into()))
+        );
+        assert_eq!(
+            client.try_join_game(&open_id, &p2, &token),
+            Err(Ok(Error::Paused.into()))
+        );
+        
+        // The in-flight game still settles and the open one can be withdrawn
+        let game = reveal_and_finalize(&client, live_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(game.winner, Some(p1.clone()));
+        client.cancel_game(&open_id, &token);
+        assert_eq!(token_client.balance(&p1), 1_100);
+        
+        client.set_paused(&false);
+        client.create_game(&p1, &GameConfig::new(100, &token));
+    }
+    
+    #[test]
+    fn test_sudden_death_replays_drawn_game_for_same_pot() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig { draw_policy: DrawPolicy::SuddenDeath, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        
+        // The draw keeps the pot in play and wipes the commitments
+        let game = play_round(&client, game_id, &token, &p1, &p2, 3, 3);
+        assert_eq!(game.state, GameState::WaitingForPlayer);
+        assert_eq!(game.current_round, 2);
+        assert_eq!(game.p1_move, 0);
+        assert_eq!(game.p1_commitment, BytesN::from_array(&env, &[0u8; 32]));
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(token_client.balance(&p2), 900);
+        
+        let game = play_round(&client, game_id, &token, &p1, &p2, 2, 3);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_player(&p1).unwrap().draws, 0);
+    }
+    
+    #[test]
+    fn test_split_pot_draw_halves_pot() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig { draw_policy: DrawPolicy::SplitPot, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        let game = play_round(&client, game_id, &token, &p1, &p2, 1, 1);
+        assert_eq!(game.outcome, GameOutcome::Draw);
+        assert_eq!(token_client.balance(&p1), 1_000);
+        assert_eq!(token_client.balance(&p2), 1_000);
+        assert_eq!(client.get_player(&p2).unwrap().draws, 1);
+        
+        // An odd pot leaves the extra stroop with player one
+        assert_eq!(StellarDuelsContract::split_pot(201), (101, 100));
+        assert_eq!(StellarDuelsContract::split_pot(1), (1, 0));
+    }
+    
+    #[test]
+    fn test_set_player_name() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let player = Address::generate(&env);
+        assert_eq!(client.register_player(&player).name, String::from_str(&env, ""));
+        
+        let name = String::from_str(&env, "stellar_knight");
+        client.set_player_name(&player, &name);
+        assert_eq!(client.get_player(&player).unwrap().name, name);
+        
+        // 32 bytes fit, 33 don't
+        let longest = String::from_str(&env, "abcdefghijklmnopqrstuvwxyz012345");
+        client.set_player_name(&player, &longest);
+        let too_long = String::from_str(&env, "abcdefghijklmnopqrstuvwxyz0123456");
+        assert_eq!(
+            client.try_set_player_name(&player, &too_long).err(),
+            Some(Ok(Error::InvalidArgument.into()))
+        );
+        assert_eq!(client.get_player(&player).unwrap().name, longest);
+        
+        assert_eq!(
+            client.try_set_player_name(&Address::generate(&env), &name).err(),
+            Some(Ok(Error::PlayerNotRegistered.into()))
+        );
+    }
+    
+    #[test]
+    fn test_expire_open_game_after_expiry() {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Keep entries live across the week-long jump
+        env.ledger().with_mut(|ledger| ledger.min_persistent_entry_ttl = 2 * OPEN_GAME_EXPIRY);
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        env.ledger().set_sequence_number(100);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        let joined_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&joined_id, &p2, &token);
+        
+        env.ledger().set_sequence_number(100 + OPEN_GAME_EXPIRY);
+        assert_eq!(
+            client.try_expire_open_game(&game_id, &token),
+            Err(Ok(Error::DeadlineNotReached.into()))
+        );
+        
+        env.ledger().set_sequence_number(101 + OPEN_GAME_EXPIRY);
+        let game = client.expire_open_game(&game_id, &token);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.outcome, GameOutcome::Expired);
+        assert!(published(&client, symbol_short!("expired"), (game_id, 100i128)));
+        assert_eq!(token_client.balance(&p1), 900);
+        assert_eq!(client.get_active_games(), Vec::from_array(&env, [joined_id]));
+        
+        // Joined games are never expired this way
+        assert_eq!(
+            client.try_expire_open_game(&joined_id, &token),
+            Err(Ok(Error::AlreadyJoined.into()))
+        );
+        assert_eq!(
+            client.try_expire_open_game(&game_id, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+    }
+    
+    #[test]
+    fn test_compute_commitment_matches_hand_computed_hash() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(game_id, 1);
+        
+        // sha256(be_u64(1) || be_u32(2) || [7; 32])
+        let expected = BytesN::from_array(&env, &[
+            0x10, 0x04, 0x72, 0x15, 0x7c, 0x69, 0xe3, 0xe1, 0x31, 0x8c, 0x38, 0x19, 0xf7, 0x95, 0xde, 0x4c,
+            0x48, 0x31, 0x18, 0xb0, 0xda, 0xb3, 0xd4, 0xaa, 0xc6, 0x88, 0x7f, 0x26, 0x96, 0x43, 0xde, 0x8d,
+        ]);
+        let salt = BytesN::from_array(&env, &[7u8; 32]);
+        assert_eq!(client.compute_commitment(&game_id, &2, &salt), expected);
+        
+        // Moves outside the game's move set are rejected
+        assert_eq!(
+            client.try_compute_commitment(&game_id, &0, &salt),
+            Err(Ok(Error::InvalidMove.into()))
+        );
+        assert_eq!(
+            client.try_compute_commitment(&game_id, &4, &salt),
+            Err(Ok(Error::InvalidMove.into()))
+        );
+        assert_eq!(client.try_compute_commitment(&99, &2, &salt), Err(Ok(Error::GameNotFound.into())));
+    }
+    
+    #[test]
+    fn test_finalize_many_skips_games_not_ready() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        
+        let open_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        let ready_a = committed_game(&client, &token, &p1, &p2, 1, 2);
+        let half_revealed = committed_game(&client, &token, &p1, &p2, 1, 2);
+        let ready_b = committed_game(&client, &token, &p1, &p2, 3, 1);
+        client.reveal_move(&half_revealed, &p1, &1, &p1_salt);
+        for (game_id, p1_move, p2_move) in [(ready_a, 1, 2), (ready_b, 3, 1)] {
+            client.reveal_move(&game_id, &p1, &p1_move, &p1_salt);
+            client.reveal_move(&game_id, &p2, &p2_move, &p2_salt);
+        }
+        
+        let batch = Vec::from_array(&env, [open_id, ready_a, half_revealed, 99, ready_b]);
+        let finalized = client.finalize_many(&batch, &token);
+        assert_eq!(finalized, Vec::from_array(&env, [ready_a, ready_b]));
+        
+        assert_eq!(client.get_game(&ready_b).unwrap().state, GameState::Completed);
+        assert_eq!(client.get_game(&half_revealed).unwrap().state, GameState::MovesCommitted);
+        assert_eq!(client.get_game(&open_id).unwrap().state, GameState::WaitingForPlayer);
+        // Player one staked four games and won both finalized pots
+        assert_eq!(token_client.balance(&p1), 1_000 - 4 * 100 + 2 * 200);
+        
+        // A batch in another token finalizes nothing
+        let other = create_token(&env);
+        assert_eq!(client.finalize_many(&Vec::from_array(&env, [half_revealed]), &other).len(), 0);
+    }
+    
+    #[test]
+    fn test_move_counts_tally_reveals() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let zeros = Vec::from_array(&env, [0u32; 5]);
+        assert_eq!(client.get_player(&p1).unwrap().move_counts, zeros);
+        
+        play_game(&client, &token, &p1, &p2, 10, 1, 2);
+        play_game(&client, &token, &p1, &p2, 10, 1, 3);
+        play_game(&client, &token, &p1, &p2, 10, 3, 3);
+        
+        // Extended moves are counted in the same list
+        let config = GameConfig { ruleset: Ruleset::Extended, ..GameConfig::new(10, &token) };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        play_round(&client, game_id, &token, &p1, &p2, 5, 4);
+        
+        assert_eq!(client.get_player(&p1).unwrap().move_counts, Vec::from_array(&env, [2u32, 0, 1, 0, 1]));
+        assert_eq!(client.get_player(&p2).unwrap().move_counts, Vec::from_array(&env, [0u32, 1, 2, 1, 0]));
+    }
+    
+    #[test]
+    fn test_use_balance_game_stakes_and_pays_internally() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        assert_eq!(client.deposit(&p1, &300, &token), 300);
+        assert_eq!(client.deposit(&p2, &100, &token), 100);
+        
+        let config = GameConfig { use_balance: true, ..GameConfig::new(100, &token) };
+        let game_id = client.create_game(&p1, &config);
+        assert!(client.get_game(&game_id).unwrap().settle_internal);
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(client.get_balance(&p1, &token), 200);
+        assert_eq!(client.get_balance(&p2, &token), 0);
+        assert_eq!(token_client.balance(&p1), 700);
+        
+        // The pot lands in the winner's internal balance, ready to withdraw
+        play_round(&client, game_id, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.get_balance(&p1, &token), 400);
+        assert_eq!(client.withdraw(&p1, &400, &token), 0);
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(client.reconcile(&token), (0, 0));
+        
+        // Joining needs enough balance; stakes can't be both internal and per-round
+        client.deposit(&p1, &100, &token);
+        let game_id = client.create_game(&p1, &config);
+        assert_eq!(
+            client.try_join_game(&game_id, &p2, &token),
+            Err(Ok(Error::InsufficientFunds.into()))
+        );
+        assert_eq!(
+            client.try_validate_game_config(&GameConfig { round_stakes: true, ..config }),
+            Err(Ok(Error::IncompatibleOptions))
+        );
+    }
+    
+    #[test]
+    fn test_win_streaks() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let streaks = |player: &Address| {
+            let profile = client.get_player(player).unwrap();
+            (profile.current_win_streak, profile.best_win_streak)
+        };
+        
+        play_game(&client, &token, &p1, &p2, 10, 1, 2);
+        play_game(&client, &token, &p1, &p2, 10, 1, 2);
+        play_game(&client, &token, &p1, &p2, 10, 2, 1);
+        play_game(&client, &token, &p1, &p2, 10, 1, 2);
+        assert_eq!(streaks(&p1), (1, 2));
+        assert_eq!(streaks(&p2), (0, 1));
+        
+        // Draws keep the streak alive unless the admin says otherwise
+        play_game(&client, &token, &p1, &p2, 10, 3, 3);
+        assert_eq!(streaks(&p1), (1, 2));
+        client.set_draws_reset_streaks(&true);
+        play_game(&client, &token, &p1, &p2, 10, 3, 3);
+        assert_eq!(streaks(&p1), (0, 2));
+    }
+    
+    #[test]
+    fn test_token_whitelist() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let token = create_token(&env);
+        let rogue = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        StellarAssetClient::new(&env, &rogue).mint(&p1, &1_000);
+        
+        // Every token is accepted until the admin starts a whitelist
+        assert!(client.is_token_allowed(&rogue));
+        client.create_game(&p1, &GameConfig::new(100, &rogue));
+        
+        client.add_allowed_token(&token);
+        assert!(client.is_token_allowed(&token));
+        assert!(!client.is_token_allowed(&rogue));
+        client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(
+            client.try_create_game(&p1, &GameConfig::new(100, &rogue)),
+            Err(Ok(Error::TokenNotAllowed.into()))
+        );
+        
+        // Removing the last token doesn't reopen the lobby to every token
+        client.remove_allowed_token(&token);
+        assert!(!client.is_token_allowed(&token));
+        assert_eq!(
+            client.try_create_game(&p1, &GameConfig::new(100, &token)),
+            Err(Ok(Error::TokenNotAllowed.into()))
+        );
+    }
+    
+    #[test]
+    fn test_forfeit_awards_pot_and_win_to_opponent() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &500);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let stranger = funded_player(&env, &client, &token, 1_000);
+        
+        // Nobody to concede to before the second seat is taken
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(client.try_forfeit(&game_id, &p1, &token), Err(Ok(Error::NoOpponent.into())));
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(
+            client.try_forfeit(&game_id, &stranger, &token),
+            Err(Ok(Error::NotAuthorized.into()))
+        );
+        
+        let game = client.forfeit(&game_id, &p2, &token);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.outcome, GameOutcome::Forfeited);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(token_client.balance(&p2), 900);
+        assert_eq!(client.get_player(&p1).unwrap().wins, 1);
+        assert_eq!(client.get_player(&p2).unwrap().losses, 1);
+        assert_eq!(client.try_forfeit(&game_id, &p1, &token), Err(Ok(Error::InvalidGameState.into())));
+        
+        // Conceding during the reveal phase works the same way
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        assert_eq!(client.forfeit(&game_id, &p1, &token).winner, Some(p2.clone()));
+        assert_eq!(token_client.balance(&p2), 1_000);
+        assert_eq!(client.get_active_games_count(), 0);
+    }
+    
+    #[test]
+    fn test_activity_keeps_game_storage_alive() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        // Far past the default minimum TTL, but within the extension
+        let start = env.ledger().sequence();
+        env.ledger().set_sequence_number(start + GAME_TTL_EXTEND_TO - GAME_TTL_THRESHOLD);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p1_salt),
+        );
+        
+        // The commit pushed the expiry out again
+        env.ledger().set_sequence_number(start + GAME_TTL_EXTEND_TO + 1_000);
+        let game = client.get_game(&game_id).unwrap();
+        assert_ne!(game.p1_commitment, BytesN::from_array(&env, &[0u8; 32]));
+        assert_eq!(client.get_active_games().len(), 1);
+        assert_eq!(client.get_player(&p2).unwrap().wins, 0);
+    }
+    
+    #[test]
+    fn test_get_games_by_state() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let open_a = client.create_game(&p1, &GameConfig::new(10, &token));
+        let committed = committed_game(&client, &token, &p1, &p2, 1, 2);
+        let open_b = client.create_game(&p1, &GameConfig::new(10, &token));
+        play_game(&client, &token, &p1, &p2, 10, 1, 2);
+        
+        let waiting = GameState::WaitingForPlayer;
+        assert_eq!(client.get_games_by_state(&waiting, &0, &10), Vec::from_array(&env, [open_a, open_b]));
+        assert_eq!(client.get_games_by_state(&waiting, &1, &10), Vec::from_array(&env, [open_b]));
+        assert_eq!(client.get_games_by_state(&waiting, &0, &1), Vec::from_array(&env, [open_a]));
+        assert_eq!(
+            client.get_games_by_state(&GameState::MovesCommitted, &0, &10),
+            Vec::from_array(&env, [committed])
+        );
+        // Finished games leave the active list
+        assert_eq!(client.get_games_by_state(&GameState::Completed, &0, &10).len(), 0);
+    }
+    
+    #[test]
+    fn test_game_result_recorded_on_settlement() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 3, 2);
+        assert_eq!(client.get_game_result(&game_id), None);
+        env.ledger().set_sequence_number(42);
+        let game = reveal_and_finalize(&client, game_id, &token, &p1, &p2, 3, 2);
+        
+        assert_eq!(
+            client.get_game_result(&game_id),
+            Some(GameResult {
+                game_id,
+                player_one: p1.clone(),
+                player_two: p2.clone(),
+                p1_move: game.p1_move,
+                p2_move: game.p2_move,
+                winner: game.winner,
+                pot: 200,
+                finalized_ledger: 42,
+            })
+        );
+        assert_eq!(client.get_game_result(&game_id).unwrap().winner, Some(p2.clone()));
+        
+        // Forfeits are recorded too, with whatever was revealed
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        client.forfeit(&game_id, &p2, &token);
+        let result = client.get_game_result(&game_id).unwrap();
+        assert_eq!((result.p1_move, result.p2_move, result.winner), (0, 0, Some(p1)));
+    }
+
+    #[test]
+    fn test_four_player_tournament_pays_champion() {
+        let env = Env::default();
+        env.mock_all_auths();
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let organizer = Address::generate(&env);
+        client.initialize(&organizer, &500); // 5% rake
+        
+        let players = Vec::from_array(&env, [
+            funded_player(&env, &client, &token, 100),
+            funded_player(&env, &client, &token, 100),
+            funded_player(&env, &client, &token, 100),
+            funded_player(&env, &client, &token, 100),
+        ]);
+        let tournament_id = client.create_tournament(&organizer, &100, &token, &4);
+        for player in players.iter() {
+            client.join_tournament(&tournament_id, &player, &token);
+        }
+        assert_eq!(
+            client.try_join_tournament(&tournament_id, &players.get(0).unwrap(), &token),
+            Err(Ok(Error::InvalidTournamentState.into()))
+        );
+        
+        let tournament = client.start_tournament(&tournament_id);
+        assert_eq!(tournament.round, 1);
+        assert_eq!(tournament.games.len(), 2);
+        assert_eq!(client.reconcile(&token), (400, 400));
+        
+        let (a, b, c, d) = (
+            players.get(0).unwrap(),
+            players.get(1).unwrap(),
+            players.get(2).unwrap(),
+            players.get(3).unwrap(),
+        );
+        // Semi one is drawn first and replays as sudden death
+        let semi = play_round(&client, tournament.games.get(0).unwrap(), &token, &a, &b, 1, 1);
+        assert_eq!(semi.state, GameState::WaitingForPlayer);
+        assert_eq!(
+            client.try_advance_tournament(&tournament_id, &token),
+            Err(Ok(Error::InvalidTournamentState.into()))
+        );
+        play_round(&client, semi.game_id, &token, &a, &b, 1, 2);
+        play_round(&client, tournament.games.get(1).unwrap(), &token, &c, &d, 3, 2);
+        
+        let tournament = client.advance_tournament(&tournament_id, &token);
+        assert_eq!(tournament.round, 2);
+        let final_id = tournament.games.get(0).unwrap();
+        let final_game = client.get_game(&final_id).unwrap();
+        assert_eq!(final_game.player_one, a);
+        assert_eq!(final_game.player_two, Some(d.clone()));
+        
+        play_round(&client, final_id, &token, &a, &d, 2, 1);
+        let tournament = client.advance_tournament(&tournament_id, &token);
+        assert_eq!(tournament.state, TournamentState::Finished);
+        assert_eq!(tournament.champion, Some(d.clone()));
+        
+        let token_client = token::Client::new(&env, &token);
+        assert_eq!(token_client.balance(&d), 380);
+        assert_eq!(client.get_treasury(&token), 20);
+        assert_eq!(client.reconcile(&token), (20, 20));
+    }
+    
+    #[test]
+    fn test_tournament_no_show_is_timed_out_of_the_bracket() {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Keep entries live across the day-long jump
+        env.ledger().with_mut(|ledger| ledger.min_persistent_entry_ttl = 2 * BRACKET_COMMIT_WINDOW);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let organizer = Address::generate(&env);
+        client.initialize(&organizer, &0);
+        
+        // Entrants don't need to register first
+        let a = Address::generate(&env);
+        let b = Address::generate(&env);
+        for player in [&a, &b] {
+            StellarAssetClient::new(&env, &token).mint(player, &100);
+        }
+        let tournament_id = client.create_tournament(&organizer, &100, &token, &2);
+        client.join_tournament(&tournament_id, &a, &token);
+        client.join_tournament(&tournament_id, &b, &token);
+        assert!(client.get_player(&a).is_some());
+        
+        let tournament = client.start_tournament(&tournament_id);
+        let final_id = tournament.games.get(0).unwrap();
+        let final_game = client.get_game(&final_id).unwrap();
+        assert_eq!(final_game.commit_window, BRACKET_COMMIT_WINDOW);
+        assert_eq!(final_game.reveal_window, BRACKET_REVEAL_WINDOW);
+        
+        // Player one never commits; player two claims the game and the pool
+        client.commit_move(&final_id, &b, &BytesN::from_array(&env, &[4u8; 32]));
+        env.ledger().set_sequence_number(final_game.commit_deadline + 1);
+        assert_eq!(client.claim_commit_timeout(&final_id, &token).winner, Some(b.clone()));
+        
+        let tournament = client.advance_tournament(&tournament_id, &token);
+        assert_eq!(tournament.champion, Some(b.clone()));
+        assert_eq!(token::Client::new(&env, &token).balance(&b), 200);
+    }
+
+    /// Play a drawn SuddenDeathRandom game finalized at ledger `sequence`;
+    /// returns whether player one won the flip
+    fn random_tiebreak_draw(sequence: u32) -> bool {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let config = GameConfig {
+            draw_policy: DrawPolicy::SuddenDeathRandom,
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 3, p1_salt.clone()),
+        );
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 3, p2_salt.clone()),
+        );
+        client.reveal_move(&game_id, &p1, &3, &p1_salt);
+        client.reveal_move(&game_id, &p2, &3, &p2_salt);
+        
+        env.ledger().with_mut(|l| l.sequence_number = sequence);
+        let game = client.finalize_game(&game_id, &token);
+        
+        // The flip's winner takes the whole pot as a regular win
+        let winner = game.winner.clone().unwrap();
+        assert_eq!(game.outcome, GameOutcome::Won);
+        assert_eq!(token::Client::new(&env, &token).balance(&winner), 1_100);
+        winner == p1
+    }
+    
+    #[test]
+    fn test_random_tiebreak_is_fixed_by_ledger() {
+        // Same commitments and same ledger always flip the same way
+        for sequence in 100..108u32 {
+            assert_eq!(random_tiebreak_draw(sequence), random_tiebreak_draw(sequence));
+        }
+        
+        // Only the ledger changes between these, and both seats win some
+        let p1_wins = (100..116u32).filter(|sequence| random_tiebreak_draw(*sequence)).count();
+        assert!(p1_wins > 0 && p1_wins < 16);
+    }
+
+    #[test]
+    fn test_player_totals_track_stakes_and_winnings() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        client.initialize(&Address::generate(&env), &500); // 5%
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        play_game(&client, &token, &p1, &p2, 100, 1, 2); // p1 wins 190
+        play_game(&client, &token, &p1, &p2, 200, 2, 2); // Refunded draw
+        play_game(&client, &token, &p1, &p2, 300, 2, 1); // p2 wins 570
+        
+        let one = client.get_player(&p1).unwrap();
+        let two = client.get_player(&p2).unwrap();
+        assert_eq!((one.total_staked, one.total_won), (600, 190));
+        assert_eq!((two.total_staked, two.total_won), (600, 570));
+    }
+
+    #[test]
+    fn test_recommit_before_opponent_commits() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        // Committed with the wrong salt, then fixed it before p2 committed
+        let wrong_salt = BytesN::from_array(&env, &[9u8; 32]);
+        let salt = BytesN::from_array(&env, &[1u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, wrong_salt),
+        );
+        client.recommit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, salt.clone()),
+        );
+        
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 2, p2_salt.clone()),
+        );
+        client.reveal_move(&game_id, &p1, &1, &salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        assert_eq!(client.finalize_game(&game_id, &token).winner, Some(p1));
+    }
+    
+    #[test]
+    fn test_recommit_rejected_once_both_committed() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        // Nothing to replace yet
+        let commitment = BytesN::from_array(&env, &[7u8; 32]);
+        assert_eq!(
+            client.try_recommit_move(&game_id, &p1, &commitment),
+            Err(Ok(Error::NotCommitted.into()))
+        );
+        
+        client.commit_move(&game_id, &p1, &BytesN::from_array(&env, &[3u8; 32]));
+        client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[4u8; 32]));
+        assert_eq!(
+            client.try_recommit_move(&game_id, &p1, &commitment),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!(
+            client.try_recommit_move(&game_id, &p2, &commitment),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!(client.get_game(&game_id).unwrap().state, GameState::MovesCommitted);
+    }
+
+    #[test]
+    fn test_admin_sweep_only_after_long_expiry() {
+        let env = Env::default();
+        env.mock_all_auths();
+        // Keep entries live across the multi-week jump
+        env.ledger().with_mut(|ledger| ledger.min_persistent_entry_ttl = 2 * ABANDONED_GAME_EXPIRY);
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Both committed, then both vanished; nothing else can settle it
+        env.ledger().set_sequence_number(100);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        client.commit_move(&game_id, &p1, &BytesN::from_array(&env, &[3u8; 32]));
+        client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[4u8; 32]));
+        
+        // A game with a commit deadline can still be claimed by timeout
+        let config = GameConfig { commit_window: 10, ..GameConfig::new(100, &token) };
+        let timed_id = client.create_game(&p1, &config);
+        client.join_game(&timed_id, &p2, &token);
+        
+        env.ledger().set_sequence_number(100 + ABANDONED_GAME_EXPIRY);
+        assert_eq!(
+            client.try_admin_sweep_expired(&game_id, &token),
+            Err(Ok(Error::DeadlineNotReached.into()))
+        );
+        
+        env.ledger().set_sequence_number(101 + ABANDONED_GAME_EXPIRY);
+        assert_eq!(
+            client.try_admin_sweep_expired(&timed_id, &token),
+            Err(Ok(Error::TimeoutNotAvailable.into()))
+        );
+        let game = client.admin_sweep_expired(&game_id, &token);
+        assert_eq!(game.state, GameState::Completed);
+        assert_eq!(game.outcome, GameOutcome::Swept);
+        assert_eq!(client.get_treasury(&token), 200);
+        assert!(published(&client, symbol_short!("swept"), (game_id, 200i128)));
+        assert_eq!(
+            client.try_admin_sweep_expired(&game_id, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        
+        // The swept game can't be played again either
+        assert_eq!(
+            client.try_commit_move(&game_id, &p1, &BytesN::from_array(&env, &[5u8; 32])),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!(client.get_treasury(&token), 200);
+    }
+    
+    #[test]
+    fn test_admin_sweep_counts_reveals_as_activity() {
+        let env = Env::default();
+        env.mock_all_auths();
+        env.ledger().with_mut(|ledger| ledger.min_persistent_entry_ttl = 3 * ABANDONED_GAME_EXPIRY);
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // Player one revealed well after the commits; that keeps the game live
+        env.ledger().set_sequence_number(100);
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        env.ledger().set_sequence_number(100 + ABANDONED_GAME_EXPIRY);
+        client.reveal_move(&game_id, &p1, &1, &BytesN::from_array(&env, &[1u8; 32]));
+        
+        env.ledger().set_sequence_number(101 + ABANDONED_GAME_EXPIRY);
+        assert_eq!(
+            client.try_admin_sweep_expired(&game_id, &token),
+            Err(Ok(Error::DeadlineNotReached.into()))
+        );
+        
+        env.ledger().set_sequence_number(101 + 2 * ABANDONED_GAME_EXPIRY);
+        assert_eq!(client.admin_sweep_expired(&game_id, &token).outcome, GameOutcome::Swept);
+    }
+
+    #[test]
+    fn test_game_flow_errors_are_typed() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let commitment = BytesN::from_array(&env, &[5u8; 32]);
+        
+        assert_eq!(client.try_get_game_progress(&42, &p1), Err(Ok(Error::GameNotFound.into())));
+        
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        assert_eq!(
+            client.try_commit_move(&game_id, &p1, &commitment),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        client.join_game(&game_id, &p2, &token);
+        assert_eq!(
+            client.try_commit_move(&game_id, &Address::generate(&env), &commitment),
+            Err(Ok(Error::NotAuthorized.into()))
+        );
+        client.commit_move(&game_id, &p1, &commitment);
+        assert_eq!(
+            client.try_commit_move(&game_id, &p1, &commitment),
+            Err(Ok(Error::AlreadyCommitted.into()))
+        );
+    }
+
+    #[test]
+    fn test_head_to_head_record() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let a = funded_player(&env, &client, &token, 1_000);
+        let b = funded_player(&env, &client, &token, 1_000);
+        let c = funded_player(&env, &client, &token, 1_000);
+        
+        play_game(&client, &token, &a, &b, 100, 1, 2); // a wins
+        play_game(&client, &token, &b, &a, 100, 2, 1); // a wins again, seats swapped
+        play_game(&client, &token, &a, &b, 100, 3, 3); // draw
+        play_game(&client, &token, &a, &c, 100, 1, 2); // Someone else's rivalry
+        
+        assert_eq!(client.get_head_to_head(&a, &b), (2, 0, 1));
+        assert_eq!(client.get_head_to_head(&b, &a), (0, 2, 1));
+        assert_eq!(client.get_head_to_head(&a, &c), (1, 0, 0));
+        assert_eq!(client.get_head_to_head(&c, &a), (0, 1, 0));
+        assert_eq!(client.get_head_to_head(&b, &c), (0, 0, 0));
+    }
+
+    #[test]
+    fn test_rejected_joins_move_no_funds() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let p3 = funded_player(&env, &client, &token, 1_000);
+        let outsider = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&outsider, &1_000);
+        
+        let finished = play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        let open = client.create_game(&p1, &GameConfig::new(100, &token));
+        let seated = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&seated, &p2, &token);
+        let before = token_client.balance(&client.address);
+        
+        assert_eq!(
+            client.try_join_game(&finished, &p3, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!(client.try_join_game(&open, &p1, &token), Err(Ok(Error::SelfPlay.into())));
+        assert_eq!(
+            client.try_join_game(&finished, &outsider, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!(client.try_join_game(&seated, &p3, &token), Err(Ok(Error::AlreadyJoined.into())));
+        
+        assert_eq!(token_client.balance(&p3), 1_000);
+        assert_eq!(token_client.balance(&outsider), 1_000);
+        assert_eq!(token_client.balance(&client.address), before);
+    }
+
+    #[test]
+    fn test_accepted_continuation_doubles_the_pot() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        
+        // Only the round's winner may offer, and only its loser may accept
+        assert_eq!(client.try_offer_continuation(&game_id, &p2), Err(Ok(Error::NotAuthorized.into())));
+        client.offer_continuation(&game_id, &p1);
+        assert_eq!(
+            client.try_accept_continuation(&game_id, &p1, &token),
+            Err(Ok(Error::NotAuthorized.into()))
+        );
+        let game = client.accept_continuation(&game_id, &p2, &token);
+        assert_eq!(game.state, GameState::WaitingForPlayer);
+        assert_eq!(game.stake_amount, 200);
+        assert_eq!(token_client.balance(&client.address), 400);
+        
+        // p1 loses the doubled round and with it everything
+        let game = play_round(&client, game_id, &token, &p1, &p2, 3, 2);
+        assert_eq!(game.winner, Some(p2.clone()));
+        assert_eq!(token_client.balance(&p1), 800);
+        assert_eq!(token_client.balance(&p2), 1_200);
+        assert_eq!(client.get_player(&p1).unwrap().total_staked, 200);
+    }
+    
+    #[test]
+    fn test_declined_continuation_finalizes_normally() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let game_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        let p1_salt = BytesN::from_array(&env, &[1u8; 32]);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.reveal_move(&game_id, &p1, &1, &p1_salt);
+        client.reveal_move(&game_id, &p2, &2, &p2_salt);
+        client.offer_continuation(&game_id, &p1);
+        assert_eq!(
+            client.try_offer_continuation(&game_id, &p1),
+            Err(Ok(Error::ContinuationOffered.into()))
+        );
+        
+        // p2 declines by finalizing; the offer dies with the game
+        let game = client.finalize_game(&game_id, &token);
+        assert_eq!(game.winner, Some(p1.clone()));
+        assert_eq!(token_client.balance(&p1), 1_100);
+        assert_eq!(
+            client.try_accept_continuation(&game_id, &p2, &token),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+    }
+
+    #[test]
+    fn test_total_locked_matches_contract_balance() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // Ensure game has two players
-        assert!(game.player_two.is_some(), "Waiting for second player");
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
         
-        // Create zero bytes for comparison
-        let zero_commitment = BytesN::from_array(&env, &[0u8; 32]);
+        client.create_game(&p1, &GameConfig::new(100, &token)); // Open
+        let seated = client.create_game(&p1, &GameConfig::new(250, &token));
+        client.join_game(&seated, &p2, &token);
+        assert_eq!(client.get_total_locked(&token), 600);
+        assert_eq!(client.get_contract_balance(&token), 600);
         
-        // Determine if this is player 1 or player 2
-        if player == game.player_one {
-            assert_eq!(game.p1_commitment, zero_commitment, "Player 1 already committed");
-            game.p1_commitment = commitment;
-        } else if Some(player.clone()) == game.player_two {
-            assert_eq!(game.p2_commitment, zero_commitment, "Player 2 already committed");
-            game.p2_commitment = commitment;
-        } else {
-            panic!("Player not in this game");
-        }
+        // Settled games no longer count as locked
+        play_game(&client, &token, &p1, &p2, 50, 1, 2);
+        assert_eq!(client.get_total_locked(&token), 600);
+        assert_eq!(client.get_contract_balance(&token), 600);
+        assert_eq!(client.get_total_locked(&create_token(&env)), 0);
+    }
+
+    #[test]
+    fn test_handicap_winner_takes_combined_pot() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // If both players committed, advance state
-        if game.p1_commitment != zero_commitment && game.p2_commitment != zero_commitment {
-            game.state = GameState::MovesCommitted;
-        }
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let mentor = funded_player(&env, &client, &token, 1_000);
+        let beginner = funded_player(&env, &client, &token, 1_000);
         
-        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        let config = GameConfig { p2_stake: Some(100), ..GameConfig::new(300, &token) };
+        let game_id = client.create_game(&mentor, &config);
+        let game = client.join_game(&game_id, &beginner, &token);
+        assert_eq!((game.stake_amount, game.p2_stake), (300, 100));
+        assert_eq!(token_client.balance(&beginner), 900);
         
-        game
+        // The beginner's Attack beats the mentor's Defense
+        let game = play_round(&client, game_id, &token, &mentor, &beginner, 2, 1);
+        assert_eq!(game.winner, Some(beginner.clone()));
+        assert_eq!(token_client.balance(&beginner), 1_300);
+        assert_eq!(token_client.balance(&mentor), 700);
+        assert_eq!(client.get_player(&beginner).unwrap().total_staked, 100);
     }
     
-    /// Reveal your move and verify it matches the commitment
-    /// This is where the magic happens: the contract validates honesty
-    pub fn reveal_move(
-        env: Env,
-        game_id: u64,
-        player: Address,
-        move_choice: u32,
-        salt: BytesN<32>,
-    ) -> Game {
-        player.require_auth();
+    #[test]
+    fn test_handicap_draw_refunds_each_stake() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        let mut game: Game = env.storage()
-            .persistent()
-            .get(&DataKey::Game(game_id))
-            .expect("Game not found");
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let mentor = funded_player(&env, &client, &token, 1_000);
+        let beginner = funded_player(&env, &client, &token, 1_000);
         
-        assert_eq!(game.state, GameState::MovesCommitted, "Not ready for reveals");
+        let config = GameConfig { p2_stake: Some(100), ..GameConfig::new(300, &token) };
+        let game_id = client.create_game(&mentor, &config);
+        client.join_game(&game_id, &beginner, &token);
+        play_round(&client, game_id, &token, &mentor, &beginner, 3, 3);
+        assert_eq!(token_client.balance(&mentor), 1_000);
+        assert_eq!(token_client.balance(&beginner), 1_000);
         
-        // Validate move choice
-        assert!(move_choice >= 1 && move_choice <= 3, "Invalid move (must be 1, 2, or 3)");
+        // Halving a handicapped pot would shift stake from one player to the other
+        let split = GameConfig { draw_policy: DrawPolicy::SplitPot, ..config.clone() };
+        assert_eq!(client.try_create_game(&mentor, &split), Err(Ok(Error::IncompatibleOptions.into())));
+        let free = GameConfig { p2_stake: Some(0), ..config };
+        assert_eq!(client.try_create_game(&mentor, &free), Err(Ok(Error::InvalidStake.into())));
+    }
+
+    #[test]
+    fn test_partial_reveal_timeout_splits_by_penalty() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // Calculate what the commitment should be
-        let calculated_commitment = Self::calculate_commitment(&env, move_choice, salt);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
         
-        // Verify and store the revealed move
-        if player == game.player_one {
-            assert_ne!(game.p1_commitment, BytesN::from_array(&env, &[0u8; 32]), "No commitment found");
-            assert_eq!(calculated_commitment, game.p1_commitment, "Move does not match commitment");
-            game.p1_move = move_choice;
-        } else if Some(player.clone()) == game.player_two {
-            assert_ne!(game.p2_commitment, BytesN::from_array(&env, &[0u8; 32]), "No commitment found");
-            assert_eq!(calculated_commitment, game.p2_commitment, "Move does not match commitment");
-            game.p2_move = move_choice;
-        } else {
-            panic!("Player not in this game");
-        }
+        let config = GameConfig {
+            reveal_window: 20,
+            reveal_penalty_bps: 2_500, // 25% of the no-show's stake
+            ..GameConfig::new(100, &token)
+        };
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        let p2_salt = BytesN::from_array(&env, &[2u8; 32]);
+        client.commit_move(&game_id, &p1, &BytesN::from_array(&env, &[9u8; 32]));
+        let game = client.commit_move(
+            &game_id,
+            &p2,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, p2_salt.clone()),
+        );
+        client.reveal_move(&game_id, &p2, &1, &p2_salt);
         
-        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        env.ledger().set_sequence_number(game.reveal_deadline + 1);
+        // Penalty games don't hand the revealer the whole pot
+        assert_eq!(
+            client.try_claim_reveal_timeout(&game_id, &token),
+            Err(Ok(Error::TimeoutNotAvailable.into()))
+        );
+        let game = client.claim_reveal_timeout_partial(&game_id, &token);
+        assert_eq!(game.outcome, GameOutcome::TimedOut);
+        assert_eq!(game.winner, Some(p2.clone()));
+        assert_eq!(token_client.balance(&p2), 1_025);
+        assert_eq!(token_client.balance(&p1), 975);
+        assert_eq!(client.get_player(&p2).unwrap().total_won, 125);
         
-        game
+        let harsh = GameConfig { reveal_penalty_bps: 10_001, ..config.clone() };
+        assert_eq!(client.try_create_game(&p1, &harsh), Err(Ok(Error::InvalidPenalty.into())));
+        let no_deadline = GameConfig { reveal_window: 0, ..config };
+        assert_eq!(
+            client.try_create_game(&p1, &no_deadline),
+            Err(Ok(Error::IncompatibleOptions.into()))
+        );
     }
-    
-    // ========================================================================
-    // GAME RESOLUTION
-    // ========================================================================
-    
-    /// Finalize the game: determine winner and distribute prizes
-    pub fn finalize_game(
-        env: Env,
-        game_id: u64,
-        token_address: Address,
-    ) -> Game {
-        let mut game: Game = env.storage()
-            .persistent()
-            .get(&DataKey::Game(game_id))
-            .expect("Game not found");
-        
-        assert_eq!(game.state, GameState::MovesCommitted, "Game not ready to finalize");
+
+    #[test]
+    fn test_create_and_join_register_new_players() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // Both moves must be revealed (non-zero)
-        assert!(game.p1_move > 0, "Player 1 hasn't revealed");
-        assert!(game.p2_move > 0, "Player 2 hasn't revealed");
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let creator = Address::generate(&env);
+        let joiner = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&creator, &1_000);
+        StellarAssetClient::new(&env, &token).mint(&joiner, &1_000);
         
-        let p1_move = game.p1_move;
-        let p2_move = game.p2_move;
+        let game_id = client.create_game(&creator, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &joiner, &token);
+        for player in [&creator, &joiner] {
+            let profile = client.get_player(player).unwrap();
+            assert_eq!((profile.wins, profile.losses, profile.draws), (0, 0, 0));
+            assert_eq!(profile.rating, DEFAULT_RATING);
+        }
         
-        // Determine winner using game logic
-        let winner_addr = Self::determine_winner(&game, p1_move, p2_move);
+        // Registering afterwards just returns the existing profile
+        play_round(&client, game_id, &token, &creator, &joiner, 1, 2);
+        assert_eq!(client.register_player(&creator).wins, 1);
+    }
+
+    #[test]
+    fn test_season_stats_are_kept_per_season() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        game.winner = winner_addr.clone();
-        game.state = GameState::Completed;
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        client.initialize(&Address::generate(&env), &0);
+        let a = funded_player(&env, &client, &token, 1_000);
+        let b = funded_player(&env, &client, &token, 1_000);
         
-        // Distribute prizes
-        let token_client = token::Client::new(&env, &token_address);
-        let total_pot = game.stake_amount * 2;
+        play_game(&client, &token, &a, &b, 100, 1, 2); // a wins
+        play_game(&client, &token, &a, &b, 100, 3, 3); // draw
+        client.start_season();
+        play_game(&client, &token, &a, &b, 100, 2, 1); // b wins
         
-        if let Some(winner) = &winner_addr {
-            // Winner takes all
-            token_client.transfer(
-                &env.current_contract_address(),
-                winner,
-                &total_pot,
-            );
-            
-            // Update player stats
-            Self::update_player_stats(&env, &game.player_one, winner == &game.player_one);
-            let p2 = game.player_two.as_ref().unwrap();
-            Self::update_player_stats(&env, p2, winner == p2);
-        } else {
-            // Draw - refund both players
-            token_client.transfer(
-                &env.current_contract_address(),
-                &game.player_one,
-                &game.stake_amount,
-            );
-            let p2 = game.player_two.as_ref().unwrap();
-            token_client.transfer(
-                &env.current_contract_address(),
-                p2,
-                &game.stake_amount,
-            );
-            
-            // Update stats for draw
-            Self::increment_draws(&env, &game.player_one);
-            Self::increment_draws(&env, p2);
-        }
+        let first = client.get_player_season(&a, &0);
+        assert_eq!((first.wins, first.losses, first.draws), (1, 0, 1));
+        let second = client.get_player_season(&a, &1);
+        assert_eq!((second.wins, second.losses, second.draws), (0, 1, 0));
+        let second = client.get_player_season(&b, &1);
+        assert_eq!((second.wins, second.losses, second.draws), (1, 0, 0));
+        assert_eq!(client.get_player_season(&a, &2), SeasonStats::default());
         
-        // Remove from active games
-        Self::remove_from_active_games(&env, game_id);
+        // Lifetime stats span both seasons
+        let lifetime = client.get_player(&a).unwrap();
+        assert_eq!((lifetime.wins, lifetime.losses, lifetime.draws), (1, 1, 1));
+    }
+
+    #[test]
+    fn test_zero_salt_reveal_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        env.storage().persistent().set(&DataKey::Game(game_id), &game);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
         
-        game
+        let zero_salt = BytesN::from_array(&env, &[0u8; 32]);
+        client.commit_move(
+            &game_id,
+            &p1,
+            &StellarDuelsContract::calculate_commitment(&env, game_id, 1, zero_salt.clone()),
+        );
+        client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[4u8; 32]));
+        assert_eq!(
+            client.try_reveal_move(&game_id, &p1, &1, &zero_salt),
+            Err(Ok(Error::WeakSalt.into()))
+        );
     }
-    
-    // ========================================================================
-    // HELPER FUNCTIONS (PRIVATE LOGIC)
-    // ========================================================================
-    
-    /// Calculate the commitment hash from move and salt
-    /// This is the same calculation players do off-chain
-    fn calculate_commitment(env: &Env, move_id: u32, salt: BytesN<32>) -> BytesN<32> {
-        // Convert move_id to 4 bytes (big-endian)
-        let move_bytes = move_id.to_be_bytes();
+
+    #[test]
+    fn test_player_rank_follows_wins() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // Create 36-byte array: 4 bytes (move) + 32 bytes (salt)
-        let mut data = [0u8; 36];
-        data[..4].copy_from_slice(&move_bytes);
-        data[4..].copy_from_slice(salt.to_array().as_ref());
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let a = funded_player(&env, &client, &token, 1_000);
+        let b = funded_player(&env, &client, &token, 1_000);
+        let c = funded_player(&env, &client, &token, 1_000);
         
-        // Hash the concatenated data
-        let bytes = soroban_sdk::Bytes::from_array(env, &data);
-        env.crypto().sha256(&bytes).into()
+        play_game(&client, &token, &b, &a, 100, 1, 2); // b: 1 win
+        play_game(&client, &token, &c, &a, 100, 1, 2); // c: 1 win
+        play_game(&client, &token, &c, &b, 100, 1, 2); // c: 2 wins
+        
+        assert_eq!(client.get_player_rank(&c), Some(1));
+        assert_eq!(client.get_player_rank(&b), Some(2));
+        assert_eq!(client.get_player_rank(&a), None); // No wins, not on the board
+        assert_eq!(client.get_player_rank(&Address::generate(&env)), None);
     }
-    
-    /// Game logic: determine winner based on moves
-    /// Returns Some(Address) for winner, None for draw
-    fn determine_winner(game: &Game, p1_move: u32, p2_move: u32) -> Option<Address> {
-        if p1_move == p2_move {
-            return None; // Draw
-        }
+
+    /// Season leaderboard after `steady` wins twice and `drawish` wins once
+    /// and draws three times, under the given draw scoring
+    fn leaderboard_under(scoring: DrawScoring) -> (Vec<(Address, u32)>, Address, Address) {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // Attack (1) beats Defense (2), Defense (2) beats Magic (3), Magic (3) beats Attack (1)
-        let p1_wins = match (p1_move, p2_move) {
-            (1, 2) => true,  // Attack beats Defense
-            (2, 3) => true,  // Defense beats Magic
-            (3, 1) => true,  // Magic beats Attack
-            _ => false,
-        };
+        let client = setup_contract(&env);
+        client.initialize(&Address::generate(&env), &0);
+        client.set_draw_scoring(&scoring);
+        let token = create_token(&env);
+        let steady = funded_player(&env, &client, &token, 1_000);
+        let drawish = funded_player(&env, &client, &token, 1_000);
+        let rival = funded_player(&env, &client, &token, 1_000);
         
-        if p1_wins {
-            Some(game.player_one.clone())
-        } else {
-            game.player_two.clone()
+        play_game(&client, &token, &steady, &rival, 10, 1, 2);
+        play_game(&client, &token, &steady, &rival, 10, 1, 2);
+        play_game(&client, &token, &drawish, &rival, 10, 1, 2);
+        let rating_after_win = client.get_player(&drawish).unwrap().rating;
+        for _ in 0..3 {
+            play_game(&client, &token, &drawish, &rival, 10, 3, 3);
         }
+        
+        // Ignored draws leave ratings exactly where the win put them
+        let rating = client.get_player(&drawish).unwrap().rating;
+        assert_eq!(rating == rating_after_win, scoring == DrawScoring::Ignore);
+        assert_eq!(client.get_player(&drawish).unwrap().draws, 3);
+        
+        (client.get_leaderboard(), steady, drawish)
     }
     
-    /// Update player win/loss statistics
-    fn update_player_stats(env: &Env, player_addr: &Address, won: bool) {
-        let key = DataKey::Player(player_addr.clone());
-        let mut player: Player = env.storage()
-            .persistent()
-            .get(&key)
-            .expect("Player not found");
+    #[test]
+    fn test_draw_scoring_changes_leaderboard_order() {
+        let (board, steady, drawish) = leaderboard_under(DrawScoring::Ignore);
+        assert_eq!(board.get(0).unwrap(), (steady.clone(), 2));
+        assert_eq!(board.get(1).unwrap(), (drawish.clone(), 1));
         
-        if won {
-            player.wins += 1;
-        } else {
-            player.losses += 1;
-        }
+        // 1 win + 3 draws is 2.5 wins, ahead of 2 clean wins
+        let (board, steady, drawish) = leaderboard_under(DrawScoring::HalfWin);
+        assert_eq!(board.get(0).unwrap(), (drawish, 5));
+        assert_eq!(board.get(1).unwrap(), (steady, 4));
+    }
+
+    #[test]
+    fn test_pending_games_list_whose_turn_it_is() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        env.storage().persistent().set(&key, &player);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        // p2 still has to reveal; p1 already did
+        let reveal_id = committed_game(&client, &token, &p1, &p2, 1, 2);
+        client.reveal_move(&reveal_id, &p1, &1, &BytesN::from_array(&env, &[1u8; 32]));
+        // Freshly seated: both have to commit
+        let fresh_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&fresh_id, &p2, &token);
+        // Open and finished games need nothing from anyone
+        client.create_game(&p1, &GameConfig::new(100, &token));
+        play_game(&client, &token, &p1, &p2, 100, 1, 2);
+        
+        assert_eq!(client.get_pending_for_player(&p2), Vec::from_array(&env, [reveal_id, fresh_id]));
+        assert_eq!(client.get_pending_for_player(&p1), Vec::from_array(&env, [fresh_id]));
+        
+        client.commit_move(&fresh_id, &p1, &BytesN::from_array(&env, &[3u8; 32]));
+        assert!(client.get_pending_for_player(&p1).is_empty());
     }
-    
-    /// Increment draw count for player
-    fn increment_draws(env: &Env, player_addr: &Address) {
-        let key = DataKey::Player(player_addr.clone());
-        let mut player: Player = env.storage()
-            .persistent()
-            .get(&key)
-            .expect("Player not found");
+
+    #[test]
+    fn test_copied_commitment_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        player.draws += 1;
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
         
-        env.storage().persistent().set(&key, &player);
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        let commitment = BytesN::from_array(&env, &[7u8; 32]);
+        client.commit_move(&game_id, &p1, &commitment);
+        assert_eq!(
+            client.try_commit_move(&game_id, &p2, &commitment),
+            Err(Ok(Error::DuplicateCommitment.into()))
+        );
+        
+        // A commitment of their own still goes through
+        let game = client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[8u8; 32]));
+        assert_eq!(game.state, GameState::MovesCommitted);
     }
     
-    /// Get and increment the game counter (atomic operation)
-    fn get_and_increment_counter(env: &Env) -> u64 {
-        let key = DataKey::GameCounter;
-        let counter: u64 = env.storage()
-            .persistent()
-            .get(&key)
-            .unwrap_or(1);
+    #[test]
+    fn test_empty_commitment_rejected() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        env.storage().persistent().set(&key, &(counter + 1));
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
         
-        counter
+        let game_id = client.create_game(&p1, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &p2, &token);
+        
+        // Neither player has committed, so both stored commitments are zero too
+        let zero = BytesN::from_array(&env, &[0u8; 32]);
+        assert_eq!(client.try_commit_move(&game_id, &p1, &zero), Err(Ok(Error::EmptyCommitment.into())));
+        assert!(!client.get_game_progress(&game_id, &p1).p1_committed);
     }
-    
-    /// Add game to active games list
-    fn add_to_active_games(env: &Env, game_id: u64) {
-        let key = DataKey::ActiveGames;
-        let mut active: Vec<u64> = env.storage()
-            .persistent()
-            .get(&key)
-            .unwrap_or(Vec::new(env));
+
+    #[test]
+    fn test_hidden_progress_commit_publishes_nothing_until_sealed() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        active.push_back(game_id);
-        env.storage().persistent().set(&key, &active);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let mut config = GameConfig::new(100, &token);
+        config.hide_progress = true;
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        
+        client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[7u8; 32]));
+        let committed_events = env.events().all().iter().filter(|(_, topics, _)| {
+            *topics == (EVENT_TOPIC, symbol_short!("committed")).into_val(&env)
+        }).count();
+        assert_eq!(committed_events, 0);
+        
+        // Once both are in, commit status is public anyway
+        client.commit_move(&game_id, &p1, &BytesN::from_array(&env, &[8u8; 32]));
+        assert!(published(&client, symbol_short!("committed"), (game_id, p1.clone())));
     }
     
-    /// Remove game from active games list
-    fn remove_from_active_games(env: &Env, game_id: u64) {
-        let key = DataKey::ActiveGames;
-        let active: Vec<u64> = env.storage()
-            .persistent()
-            .get(&key)
-            .unwrap_or(Vec::new(env));
+    #[test]
+    fn test_hidden_progress_recommit_publishes_nothing() {
+        let env = Env::default();
+        env.mock_all_auths();
         
-        // Filter out the game_id - rebuild vec without it
-        let mut filtered = Vec::new(env);
-        for id in active.iter() {
-            if id != game_id {
-                filtered.push_back(id);
-            }
-        }
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
         
-        env.storage().persistent().set(&key, &filtered);
+        let mut config = GameConfig::new(100, &token);
+        config.hide_progress = true;
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        
+        client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[7u8; 32]));
+        client.recommit_move(&game_id, &p2, &BytesN::from_array(&env, &[9u8; 32]));
+        let recommit_events = env.events().all().iter().filter(|(_, topics, _)| {
+            *topics == (EVENT_TOPIC, symbol_short!("recommit")).into_val(&env)
+        }).count();
+        assert_eq!(recommit_events, 0);
     }
-}
-
-// ============================================================================
-// TESTS
-// ============================================================================
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use soroban_sdk::{testutils::Address as _, Address, Env};
+    #[test]
+    fn test_hidden_progress_masks_commit_timings() {
+        let env = Env::default();
+        env.mock_all_auths();
+        
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let p1 = funded_player(&env, &client, &token, 1_000);
+        let p2 = funded_player(&env, &client, &token, 1_000);
+        
+        let mut config = GameConfig::new(100, &token);
+        config.hide_progress = true;
+        let game_id = client.create_game(&p1, &config);
+        client.join_game(&game_id, &p2, &token);
+        
+        env.ledger().with_mut(|l| l.sequence_number += 5);
+        client.commit_move(&game_id, &p2, &BytesN::from_array(&env, &[7u8; 32]));
+        
+        let game = client.get_game(&game_id).unwrap();
+        assert_eq!((game.timings.p1_committed, game.timings.p2_committed), (0, 0));
+        let timings = client.get_phase_timings(&game_id);
+        assert_eq!((timings.p1_committed, timings.p2_committed), (0, 0));
+        
+        client.commit_move(&game_id, &p1, &BytesN::from_array(&env, &[8u8; 32]));
+        let timings = client.get_phase_timings(&game_id);
+        assert_eq!(timings.p2_committed, env.ledger().sequence());
+        assert_eq!(timings.p1_committed, env.ledger().sequence());
+    }
 
     #[test]
-    fn test_player_registration() {
+    fn test_quick_match_skips_reserved_games_and_registers_newcomers() {
         let env = Env::default();
-        env.mock_all_auths();  // Mock authorization for testing
+        env.mock_all_auths();
         
-        let contract_id = env.register_contract(None, StellarDuelsContract);
-        let client = StellarDuelsContractClient::new(&env, &contract_id);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let reserved_creator = funded_player(&env, &client, &token, 1_000);
+        let public_creator = funded_player(&env, &client, &token, 1_000);
+        set_rating(&client, &public_creator, 1800);
         
-        let player = Address::generate(&env);
+        // The reserved game's creator is the closer rating match
+        let mut config = GameConfig::new(100, &token);
+        config.invited = Some(Address::generate(&env));
+        client.create_game(&reserved_creator, &config);
+        let public_id = client.create_game(&public_creator, &GameConfig::new(100, &token));
         
-        let registered = client.register_player(&player);
-        assert_eq!(registered.wins, 0);
-        assert_eq!(registered.losses, 0);
-        assert_eq!(registered.draws, 0);
+        // A first-time caller with no profile
+        let newcomer = Address::generate(&env);
+        StellarAssetClient::new(&env, &token).mint(&newcomer, &1_000);
+        
+        assert_eq!(client.quick_match(&newcomer, &100, &token), Some(public_id));
+        assert!(client.get_player(&newcomer).is_some());
     }
-    
+
     #[test]
-    fn test_commitment_calculation() {
+    fn test_forfeited_game_cannot_be_replayed() {
         let env = Env::default();
+        env.mock_all_auths();
         
-        let move_id: u32 = 1; // Attack
-        let salt = BytesN::from_array(&env, &[42u8; 32]);
+        let client = setup_contract(&env);
+        let token = create_token(&env);
+        let token_client = token::Client::new(&env, &token);
+        let a = funded_player(&env, &client, &token, 100);
+        let b = funded_player(&env, &client, &token, 100);
         
-        let commitment = StellarDuelsContract::calculate_commitment(&env, move_id, salt.clone());
+        // Another game's escrow that a replay would pay out from
+        let victim = funded_player(&env, &client, &token, 1_000);
+        client.create_game(&victim, &GameConfig::new(200, &token));
         
-        // Same inputs should produce same hash
-        let commitment2 = StellarDuelsContract::calculate_commitment(&env, move_id, salt.clone());
-        assert_eq!(commitment, commitment2);
+        let game_id = client.create_game(&a, &GameConfig::new(100, &token));
+        client.join_game(&game_id, &b, &token);
+        client.forfeit(&game_id, &a, &token);
         
-        // Different move should produce different hash
-        let commitment3 = StellarDuelsContract::calculate_commitment(&env, 2, salt);
-        assert_ne!(commitment, commitment3);
+        assert_eq!(
+            client.try_commit_move(&game_id, &a, &BytesN::from_array(&env, &[1u8; 32])),
+            Err(Ok(Error::InvalidGameState.into()))
+        );
+        assert_eq!((token_client.balance(&a), token_client.balance(&b)), (0, 200));
+        assert_eq!(token_client.balance(&client.address), 200);
     }
 }